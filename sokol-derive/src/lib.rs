@@ -0,0 +1,46 @@
+/*!
+
+Derive macros for the [sokol](https://github.com/floooh/sokol) Rust bindings.
+
+The macros are re-exported by the `sokol` crate if its `derive` feature is enabled.
+
+*/
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use syn::parse_macro_input;
+use syn::DeriveInput;
+
+mod uniform;
+mod utils;
+mod vertex;
+
+/// Implements `sokol::gfx::Vertex` and `sokol::gfx::Pod` for a `#[repr(C)]` struct.
+///
+/// Fields can be annotated with `#[vertex(normalized)]` to select the
+/// normalized variant of integer vertex formats.
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    vertex::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Implements `sokol::gfx::UniformBlock` and `sokol::gfx::Pod` for a `#[repr(C)]` struct.
+///
+/// Fields can be annotated with `#[uniform(array)]` to declare an array of
+/// uniforms, and with `#[uniform(name = "...")]` to override the uniform name.
+///
+/// The fields must follow the packed uniform layout of sokol_gfx, i.e. each
+/// uniform starts right after the previous one, without padding. This is
+/// checked at compile time. It is *not* the std140 layout: no `vec3` or
+/// `vec4` alignment to 16 bytes, and no 16-byte array stride.
+#[proc_macro_derive(UniformBlock, attributes(uniform))]
+pub fn derive_uniform_block(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    uniform::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
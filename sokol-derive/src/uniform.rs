@@ -0,0 +1,111 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::quote_spanned;
+use syn::spanned::Spanned;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Lit;
+use syn::Meta;
+use syn::NestedMeta;
+use syn::Result;
+use syn::Type;
+
+use crate::utils;
+
+/// Same as `SG_MAX_UB_MEMBERS` in sokol_gfx.h.
+const MAX_UB_MEMBERS: usize = 16;
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let fields = utils::repr_c_fields(input, "UniformBlock")?;
+
+    if fields.len() > MAX_UB_MEMBERS {
+        return Err(Error::new_spanned(name,
+                                      format!("uniform blocks can't have more than {} members", MAX_UB_MEMBERS)));
+    }
+
+    let mut uniforms = Vec::new();
+    let mut checks = Vec::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        let member = utils::member(index, field);
+
+        let mut array = false;
+        let mut uniform_name = match &field.ident {
+            Some(ident) => ident.to_string(),
+            None => {
+                return Err(Error::new_spanned(field, "tuple struct fields need a #[uniform(name = \"...\")] attribute"));
+            }
+        };
+
+        for item in utils::field_attrs(field, "uniform")? {
+            match &item {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("array") => {
+                    array = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    match &nv.lit {
+                        Lit::Str(s) => uniform_name = s.value(),
+                        lit => return Err(Error::new_spanned(lit, "expected a string literal")),
+                    }
+                }
+                _ => {
+                    return Err(Error::new_spanned(item, "unknown uniform attribute, expected `array` or `name = \"...\"`"));
+                }
+            }
+        }
+
+        let ty = &field.ty;
+
+        let (elem, array_count) = if array {
+            match ty {
+                Type::Array(arr) => {
+                    let elem = &arr.elem;
+                    let len = &arr.len;
+                    (quote!(#elem), quote!((#len) as i32))
+                }
+                _ => {
+                    return Err(Error::new_spanned(ty, "#[uniform(array)] requires an array type"));
+                }
+            }
+        } else {
+            (quote!(#ty), quote!(1))
+        };
+
+        let uniform_type = quote_spanned!(ty.span()=> <#elem as ::sokol::gfx::Uniform>::TYPE);
+
+        uniforms.push(quote! {
+            ::sokol::gfx::SgShaderUniformDesc {
+                name: #uniform_name,
+                uniform_type: #uniform_type,
+                array_count: #array_count,
+            }
+        });
+
+        let msg = format!("`{}::{}` doesn't follow the packed uniform layout of sokol_gfx (which isn't std140)",
+                          name, uniform_name);
+
+        checks.push(quote! {
+            assert!(::std::mem::offset_of!(#name, #member) == offset, #msg);
+            offset += ::std::mem::size_of::<#ty>();
+        });
+    }
+
+    let size_msg = format!("`{}` must not contain padding bytes", name);
+
+    Ok(quote! {
+        const _: () = {
+            let mut offset = 0usize;
+            #(#checks)*
+            assert!(::std::mem::size_of::<#name>() == offset, #size_msg);
+        };
+
+        unsafe impl ::sokol::gfx::Pod for #name {}
+
+        impl ::sokol::gfx::UniformBlock for #name {
+            fn uniforms() -> ::std::vec::Vec<::sokol::gfx::SgShaderUniformDesc<'static>> {
+                vec![#(#uniforms),*]
+            }
+        }
+    })
+}
@@ -0,0 +1,86 @@
+use proc_macro2::Span;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Field;
+use syn::Member;
+use syn::Meta;
+use syn::NestedMeta;
+use syn::Result;
+
+/// Checks that the input is a non-generic `#[repr(C)]` struct with at least
+/// one field, and returns its fields.
+pub fn repr_c_fields<'a>(input: &'a DeriveInput, derive: &str) -> Result<Vec<&'a Field>> {
+    let fields = match &input.data {
+        Data::Struct(data) => data.fields.iter().collect::<Vec<_>>(),
+        _ => {
+            return Err(Error::new(Span::call_site(),
+                                  format!("{} can only be derived for structs", derive)));
+        }
+    };
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics,
+                                      format!("{} can't be derived for generic structs", derive)));
+    }
+
+    if !is_repr_c(input) {
+        return Err(Error::new_spanned(&input.ident,
+                                      format!("{} requires #[repr(C)]", derive)));
+    }
+
+    if fields.is_empty() {
+        return Err(Error::new_spanned(&input.ident,
+                                      format!("{} can't be derived for structs without fields", derive)));
+    }
+
+    Ok(fields)
+}
+
+fn is_repr_c(input: &DeriveInput) -> bool {
+    for attr in &input.attrs {
+        if !attr.path.is_ident("repr") {
+            continue;
+        }
+
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested.iter() {
+                if let NestedMeta::Meta(Meta::Path(path)) = nested {
+                    if path.is_ident("C") {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Returns the name or index of a field, to be used with `offset_of!`.
+pub fn member(index: usize, field: &Field) -> Member {
+    match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(index.into()),
+    }
+}
+
+/// Returns the nested items of all `#[name(...)]` attributes of a field.
+pub fn field_attrs(field: &Field, name: &str) -> Result<Vec<NestedMeta>> {
+    let mut items = Vec::new();
+
+    for attr in &field.attrs {
+        if !attr.path.is_ident(name) {
+            continue;
+        }
+
+        match attr.parse_meta()? {
+            Meta::List(list) => items.extend(list.nested),
+            meta => {
+                return Err(Error::new_spanned(meta, format!("expected #[{}(...)]", name)));
+            }
+        }
+    }
+
+    Ok(items)
+}
@@ -0,0 +1,70 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::quote_spanned;
+use syn::spanned::Spanned;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Meta;
+use syn::NestedMeta;
+use syn::Result;
+
+use crate::utils;
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let fields = utils::repr_c_fields(input, "Vertex")?;
+
+    let mut attrs = Vec::new();
+    let mut field_types = Vec::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        let mut normalized = false;
+
+        for item in utils::field_attrs(field, "vertex")? {
+            match &item {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("normalized") => {
+                    normalized = true;
+                }
+                _ => {
+                    return Err(Error::new_spanned(item, "unknown vertex attribute, expected `normalized`"));
+                }
+            }
+        }
+
+        let ty = &field.ty;
+        let member = utils::member(index, field);
+
+        let format = if normalized {
+            quote_spanned!(ty.span()=> <#ty as ::sokol::gfx::NormalizedVertexAttribute>::FORMAT)
+        } else {
+            quote_spanned!(ty.span()=> <#ty as ::sokol::gfx::VertexAttribute>::FORMAT)
+        };
+
+        attrs.push(quote! {
+            ::sokol::gfx::SgVertexAttrDesc {
+                buffer_index,
+                offset: ::std::mem::offset_of!(#name, #member) as i32,
+                format: #format,
+            }
+        });
+
+        field_types.push(ty);
+    }
+
+    let padding_msg = format!("`{}` must not contain padding bytes", name);
+
+    Ok(quote! {
+        const _: () = {
+            assert!(::std::mem::size_of::<#name>() == 0 #(+ ::std::mem::size_of::<#field_types>())*,
+                    #padding_msg);
+        };
+
+        unsafe impl ::sokol::gfx::Pod for #name {}
+
+        impl ::sokol::gfx::Vertex for #name {
+            fn attrs(buffer_index: i32) -> ::std::vec::Vec<::sokol::gfx::SgVertexAttrDesc> {
+                vec![#(#attrs),*]
+            }
+        }
+    })
+}
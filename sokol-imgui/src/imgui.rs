@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+use std::ffi::CString;
 use std::os::raw::c_char;
 use std::ptr::null;
 
@@ -6,7 +8,7 @@ use sokol::gfx::SgPixelFormat;
 use sys::imgui::ffi::*;
 
 pub mod ffi {
-    use std::ffi::CString;
+    use std::ffi::CStr;
     use std::os::raw::{c_char, c_int};
 
     use sokol::app::ffi::SAppEvent;
@@ -25,16 +27,14 @@ pub mod ffi {
     }
 
     impl SImGuiDesc {
-        pub fn make(desc: super::SImGuiDesc) -> Self {
-            let ini_filename = CString::new(desc.ini_filename).unwrap();
-
+        pub fn make(desc: super::SImGuiDesc, ini_filename: &CStr) -> Self {
             SImGuiDesc {
                 max_vertices: desc.max_vertices,
                 color_format: desc.color_format,
                 depth_format: desc.depth_format,
                 sample_count: desc.sample_count,
                 dpi_scale: desc.dpi_scale,
-                ini_filename: ini_filename.into_raw(),
+                ini_filename: ini_filename.as_ptr(),
                 no_default_font: desc.no_default_font,
             }
         }
@@ -60,10 +60,19 @@ pub struct SImGuiDesc<'a> {
     pub no_default_font: bool,
 }
 
+thread_local! {
+    // Dear ImGui keeps a pointer to the .ini filename until simgui_shutdown()
+    static INI_FILENAME: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
 pub fn simgui_setup(desc: SImGuiDesc) {
+    // a nul byte ends the filename, like it would in C
+    let ini_filename = CString::new(desc.ini_filename.split('\0').next().unwrap_or(""))
+        .unwrap_or_default();
     unsafe {
-        ffi::simgui_setup(&ffi::SImGuiDesc::make(desc));
+        ffi::simgui_setup(&ffi::SImGuiDesc::make(desc, &ini_filename));
     }
+    INI_FILENAME.with(|f| f.replace(Some(ini_filename)));
 }
 
 pub fn simgui_new_frame(width: i32, height: i32, delta_time: f64) {
@@ -88,6 +97,7 @@ pub fn simgui_shutdown() {
     unsafe {
         ffi::simgui_shutdown();
     }
+    INI_FILENAME.with(|f| f.replace(None));
 }
 
 pub fn imgui_begin_main_menu_bar() -> bool {
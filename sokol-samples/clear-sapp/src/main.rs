@@ -52,7 +52,7 @@ fn main() {
             gl_force_gles2: true,
             window_title: title,
             ..Default::default()
-        });
+        }).unwrap_or_else(|panic| panic.resume());
 
     std::process::exit(exit_code);
 }
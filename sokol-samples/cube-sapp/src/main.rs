@@ -2,19 +2,35 @@ extern crate nalgebra_glm as glm;
 extern crate sokol;
 
 use std::f32::consts::PI;
-use std::mem;
 
 use sokol::app::*;
 use sokol::gfx::*;
+use sokol::time::*;
 
 const SAMPLE_COUNT: i32 = 4;
 
+#[repr(C)]
+#[derive(Copy, Clone, Vertex)]
+struct PosColor {
+    pos: [f32; 3],
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, UniformBlock)]
+struct VsParams {
+    mvp: [[f32; 4]; 4],
+}
+
 #[derive(Default)]
 struct Cube {
     pipeline: SgPipeline,
     bindings: SgBindings,
+    step: FixedStep,
     rx: f32,
     ry: f32,
+    prev_rx: f32,
+    prev_ry: f32,
 }
 
 impl SApp for Cube {
@@ -22,42 +38,44 @@ impl SApp for Cube {
         sg_setup(&SgDesc {
             ..Default::default()
         });
+        stm_setup();
 
-        let vertices: [f32; 168] = [
-            -1.0, -1.0, -1.0, 1.0, 0.0, 0.0, 1.0,
-            1.0, -1.0, -1.0, 1.0, 0.0, 0.0, 1.0,
-            1.0, 1.0, -1.0, 1.0, 0.0, 0.0, 1.0,
-            -1.0, 1.0, -1.0, 1.0, 0.0, 0.0, 1.0,
-            -1.0, -1.0, 1.0, 0.0, 1.0, 0.0, 1.0,
-            1.0, -1.0, 1.0, 0.0, 1.0, 0.0, 1.0,
-            1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0,
-            -1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0,
-            -1.0, -1.0, -1.0, 0.0, 0.0, 1.0, 1.0,
-            -1.0, 1.0, -1.0, 0.0, 0.0, 1.0, 1.0,
-            -1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0,
-            -1.0, -1.0, 1.0, 0.0, 0.0, 1.0, 1.0,
-            1.0, -1.0, -1.0, 1.0, 0.5, 0.0, 1.0,
-            1.0, 1.0, -1.0, 1.0, 0.5, 0.0, 1.0,
-            1.0, 1.0, 1.0, 1.0, 0.5, 0.0, 1.0,
-            1.0, -1.0, 1.0, 1.0, 0.5, 0.0, 1.0,
-            -1.0, -1.0, -1.0, 0.0, 0.5, 1.0, 1.0,
-            -1.0, -1.0, 1.0, 0.0, 0.5, 1.0, 1.0,
-            1.0, -1.0, 1.0, 0.0, 0.5, 1.0, 1.0,
-            1.0, -1.0, -1.0, 0.0, 0.5, 1.0, 1.0,
-            -1.0, 1.0, -1.0, 1.0, 0.0, 0.5, 1.0,
-            -1.0, 1.0, 1.0, 1.0, 0.0, 0.5, 1.0,
-            1.0, 1.0, 1.0, 1.0, 0.0, 0.5, 1.0,
-            1.0, 1.0, -1.0, 1.0, 0.0, 0.5, 1.0
+        let vertices: [PosColor; 24] = [
+            PosColor { pos: [-1.0, -1.0, -1.0], color: [1.0, 0.0, 0.0, 1.0] },
+            PosColor { pos: [1.0, -1.0, -1.0], color: [1.0, 0.0, 0.0, 1.0] },
+            PosColor { pos: [1.0, 1.0, -1.0], color: [1.0, 0.0, 0.0, 1.0] },
+            PosColor { pos: [-1.0, 1.0, -1.0], color: [1.0, 0.0, 0.0, 1.0] },
+            PosColor { pos: [-1.0, -1.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] },
+            PosColor { pos: [1.0, -1.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] },
+            PosColor { pos: [1.0, 1.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] },
+            PosColor { pos: [-1.0, 1.0, 1.0], color: [0.0, 1.0, 0.0, 1.0] },
+            PosColor { pos: [-1.0, -1.0, -1.0], color: [0.0, 0.0, 1.0, 1.0] },
+            PosColor { pos: [-1.0, 1.0, -1.0], color: [0.0, 0.0, 1.0, 1.0] },
+            PosColor { pos: [-1.0, 1.0, 1.0], color: [0.0, 0.0, 1.0, 1.0] },
+            PosColor { pos: [-1.0, -1.0, 1.0], color: [0.0, 0.0, 1.0, 1.0] },
+            PosColor { pos: [1.0, -1.0, -1.0], color: [1.0, 0.5, 0.0, 1.0] },
+            PosColor { pos: [1.0, 1.0, -1.0], color: [1.0, 0.5, 0.0, 1.0] },
+            PosColor { pos: [1.0, 1.0, 1.0], color: [1.0, 0.5, 0.0, 1.0] },
+            PosColor { pos: [1.0, -1.0, 1.0], color: [1.0, 0.5, 0.0, 1.0] },
+            PosColor { pos: [-1.0, -1.0, -1.0], color: [0.0, 0.5, 1.0, 1.0] },
+            PosColor { pos: [-1.0, -1.0, 1.0], color: [0.0, 0.5, 1.0, 1.0] },
+            PosColor { pos: [1.0, -1.0, 1.0], color: [0.0, 0.5, 1.0, 1.0] },
+            PosColor { pos: [1.0, -1.0, -1.0], color: [0.0, 0.5, 1.0, 1.0] },
+            PosColor { pos: [-1.0, 1.0, -1.0], color: [1.0, 0.0, 0.5, 1.0] },
+            PosColor { pos: [-1.0, 1.0, 1.0], color: [1.0, 0.0, 0.5, 1.0] },
+            PosColor { pos: [1.0, 1.0, 1.0], color: [1.0, 0.0, 0.5, 1.0] },
+            PosColor { pos: [1.0, 1.0, -1.0], color: [1.0, 0.0, 0.5, 1.0] },
         ];
 
-        let vbuf = sg_make_buffer(
-            Some(&vertices),
+        let vbuf = sg_make_buffer_slice(
+            &vertices,
             &SgBufferDesc {
-                size: mem::size_of_val(&vertices),
                 buffer_type: SgBufferType::VertexBuffer,
                 usage: SgUsage::Immutable,
+                label: "cube-vertices",
+                ..Default::default()
             },
-        );
+        ).unwrap();
 
         let indices: [u16; 36] = [
             0, 1, 2, 0, 2, 3,
@@ -68,14 +86,14 @@ impl SApp for Cube {
             22, 21, 20, 23, 22, 20
         ];
 
-        let ibuf = sg_make_buffer(
-            Some(&indices),
+        let ibuf = sg_make_buffer_slice(
+            &indices,
             &SgBufferDesc {
-                size: mem::size_of_val(&indices),
                 buffer_type: SgBufferType::IndexBuffer,
+                label: "cube-indices",
                 ..Default::default()
             },
-        );
+        ).unwrap();
 
         let (vs_src, fs_src) = match sg_query_backend() {
             SgBackend::D3D11 => (
@@ -162,47 +180,20 @@ impl SApp for Cube {
                 ],
                 vs: SgShaderStageDesc {
                     source: Some(vs_src),
-                    uniform_blocks: vec!(
-                        SgShaderUniformBlockDesc {
-                            size: 64,
-                            uniforms: vec!(
-                                SgShaderUniformDesc {
-                                    name: "mvp",
-                                    uniform_type: SgUniformType::Mat4,
-                                    ..Default::default()
-                                }
-                            ),
-                        }
-                    ),
+                    uniform_blocks: vec!(VsParams::desc()),
                     ..Default::default()
                 },
                 fs: SgShaderStageDesc {
                     source: Some(fs_src),
                     ..Default::default()
                 },
+                label: "cube-shader",
             },
         );
 
         self.pipeline = sg_make_pipeline(
             &SgPipelineDesc {
-                layout: SgLayoutDesc {
-                    buffers: vec!(
-                        SgBufferLayoutDesc {
-                            stride: 28,
-                            ..Default::default()
-                        }
-                    ),
-                    attrs: vec!(
-                        SgVertexAttrDesc {
-                            format: SgVertexFormat::Float3,
-                            ..Default::default()
-                        },
-                        SgVertexAttrDesc {
-                            format: SgVertexFormat::Float4,
-                            ..Default::default()
-                        },
-                    ),
-                },
+                layout: PosColor::layout(0),
                 shader: shd,
                 index_type: SgIndexType::UInt16,
                 depth_stencil: SgDepthStencilState {
@@ -215,6 +206,7 @@ impl SApp for Cube {
                     sample_count: SAMPLE_COUNT,
                     ..Default::default()
                 },
+                label: "cube-pipeline",
                 ..Default::default()
             }
         );
@@ -248,22 +240,36 @@ impl SApp for Cube {
         );
         let view_proj = proj * view;
 
-        self.rx += 1.0;
-        self.ry += 2.0;
-        let rxm = glm::rotation(self.rx * PI / 180.0, &glm::vec3(1.0, 0.0, 0.0));
-        let rym = glm::rotation(self.ry * PI / 180.0, &glm::vec3(0.0, 1.0, 0.0));
+        // 60 and 120 degrees per second
+        let (mut rx, mut ry) = (self.rx, self.ry);
+        let (mut prev_rx, mut prev_ry) = (self.prev_rx, self.prev_ry);
+        self.step.advance(|dt| {
+            prev_rx = rx;
+            prev_ry = ry;
+            rx += 60.0 * dt as f32;
+            ry += 120.0 * dt as f32;
+        });
+        self.rx = rx;
+        self.ry = ry;
+        self.prev_rx = prev_rx;
+        self.prev_ry = prev_ry;
+
+        // render in between the last two updates
+        let alpha = self.step.alpha() as f32;
+        let rx = prev_rx + (rx - prev_rx) * alpha;
+        let ry = prev_ry + (ry - prev_ry) * alpha;
+        let rxm = glm::rotation(rx * PI / 180.0, &glm::vec3(1.0, 0.0, 0.0));
+        let rym = glm::rotation(ry * PI / 180.0, &glm::vec3(0.0, 1.0, 0.0));
         let model = rxm * rym;
 
-        let mvp: [[f32; 4]; 4] = (view_proj * model).into();
+        let vs_params = VsParams {
+            mvp: (view_proj * model).into(),
+        };
 
         sg_begin_default_pass(&pass_action, sapp_width(), sapp_height());
         sg_apply_pipeline(self.pipeline);
         sg_apply_bindings(&self.bindings);
-        sg_apply_uniforms(
-            SgShaderStage::Vertex,
-            0,
-            &mvp,
-            64);
+        sg_apply_uniform_block(SgShaderStage::Vertex, 0, &vs_params);
         sg_draw(0, 36, 1);
         sg_end_pass();
         sg_commit();
@@ -292,7 +298,7 @@ fn main() {
             window_title: title,
             ..Default::default()
         },
-    );
+    ).unwrap_or_else(|panic| panic.resume());
 
     std::process::exit(exit_code);
 }
@@ -118,7 +118,7 @@ fn main() {
             height: 960,
             window_title: title,
             ..Default::default()
-        });
+        }).unwrap_or_else(|panic| panic.resume());
 
     std::process::exit(exit_code);
 }
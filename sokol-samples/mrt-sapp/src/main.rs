@@ -6,12 +6,14 @@ use std::mem;
 
 use sokol::app::*;
 use sokol::gfx::*;
+use sokol::time::*;
 
 const MSAA_SAMPLES: i32 = 4;
 
 struct MRT {
-    offscreen_pass_desc: SgPassDesc,
-    offscreen_pass: SgPass,
+    offscreen_color_images: Vec<Image>,
+    offscreen_depth_image: Option<Image>,
+    offscreen_pass: Option<Pass>,
     offscreen_pipeline: SgPipeline,
     offscreen_bindings: SgBindings,
     fsq_pipeline: SgPipeline,
@@ -20,17 +22,17 @@ struct MRT {
     dbg_bindings: SgBindings,
     offscreen_pass_action: SgPassAction,
     default_pass_action: SgPassAction,
+    step: FixedStep,
     rx: f32,
     ry: f32,
+    prev_rx: f32,
+    prev_ry: f32,
 }
 
 impl MRT {
     fn create_offscreen_pass(&mut self, width: i32, height: i32) {
-        sg_destroy_pass(self.offscreen_pass);
-        for att in &self.offscreen_pass_desc.color_attachments {
-            sg_destroy_image(att.image);
-        }
-        sg_destroy_image(self.offscreen_pass_desc.depth_stencil_attachment.image);
+        // release the previous pass before the images it renders to
+        self.offscreen_pass = None;
 
         let offscreen_sample_count = if sg_query_feature(SgFeature::MSAARenderTargets) {
             MSAA_SAMPLES
@@ -47,38 +49,37 @@ impl MRT {
             wrap_u: SgWrap::ClampToEdge,
             wrap_v: SgWrap::ClampToEdge,
             sample_count: offscreen_sample_count,
+            label: "offscreen-color-image",
             ..Default::default()
         };
         let depth_img_desc = SgImageDesc {
             pixel_format: SgPixelFormat::Depth,
+            label: "offscreen-depth-image",
             ..color_img_desc
         };
-        self.offscreen_pass_desc = SgPassDesc {
-            color_attachments: vec![
-                SgAttachmentDesc {
-                    image: sg_make_image(SG_IMAGE_CONTENT_NONE, &color_img_desc),
-                    ..Default::default()
-                },
-                SgAttachmentDesc {
-                    image: sg_make_image(SG_IMAGE_CONTENT_NONE, &color_img_desc),
-                    ..Default::default()
-                },
-                SgAttachmentDesc {
-                    image: sg_make_image(SG_IMAGE_CONTENT_NONE, &color_img_desc),
+        self.offscreen_color_images = (0..3)
+            .map(|_| Image::new(SG_IMAGE_CONTENT_NONE, &color_img_desc))
+            .collect();
+        let depth_image = Image::new(SG_IMAGE_CONTENT_NONE, &depth_img_desc);
+
+        self.offscreen_pass = Some(Pass::new(&SgPassDesc {
+            color_attachments: self.offscreen_color_images.iter()
+                .map(|image| SgAttachmentDesc {
+                    image: image.raw(),
                     ..Default::default()
-                },
-            ],
+                })
+                .collect(),
             depth_stencil_attachment: SgAttachmentDesc {
-                image: sg_make_image(SG_IMAGE_CONTENT_NONE, &depth_img_desc),
+                image: depth_image.raw(),
                 ..Default::default()
             },
-        };
-        self.offscreen_pass = sg_make_pass(&self.offscreen_pass_desc);
+            label: "offscreen-pass",
+        }));
+        self.offscreen_depth_image = Some(depth_image);
 
-        self.fsq_bindings.fs_images.clear();
-        for att in &self.offscreen_pass_desc.color_attachments {
-            self.fsq_bindings.fs_images.push(att.image);
-        }
+        self.fsq_bindings.fs_images = self.offscreen_color_images.iter()
+            .map(Image::raw)
+            .collect();
     }
 }
 
@@ -87,6 +88,7 @@ impl SApp for MRT {
         sg_setup(&SgDesc {
             ..Default::default()
         });
+        stm_setup();
 
         self.create_offscreen_pass(sapp_width(), sapp_height());
 
@@ -121,6 +123,7 @@ impl SApp for MRT {
             Some(&cube_vertices),
             &SgBufferDesc {
                 size: mem::size_of_val(&cube_vertices),
+                label: "cube-vertices",
                 ..Default::default()
             },
         );
@@ -139,6 +142,7 @@ impl SApp for MRT {
             &SgBufferDesc {
                 size: mem::size_of_val(&cube_indices),
                 buffer_type: SgBufferType::IndexBuffer,
+                label: "cube-indices",
                 ..Default::default()
             },
         );
@@ -268,6 +272,7 @@ impl SApp for MRT {
                     source: Some(cube_fs_src),
                     ..Default::default()
                 },
+                label: "offscreen-shader",
             },
         );
 
@@ -311,6 +316,7 @@ impl SApp for MRT {
                     sample_count: MSAA_SAMPLES,
                     ..Default::default()
                 },
+                label: "offscreen-pipeline",
                 ..Default::default()
             }
         );
@@ -329,6 +335,7 @@ impl SApp for MRT {
             Some(&quad_vertices),
             &SgBufferDesc {
                 size: mem::size_of_val(&quad_vertices),
+                label: "quad-vertices",
                 ..Default::default()
             },
         );
@@ -489,6 +496,7 @@ impl SApp for MRT {
                     ],
                     ..Default::default()
                 },
+                label: "fsq-shader",
             },
         );
 
@@ -509,17 +517,16 @@ impl SApp for MRT {
                     sample_count: MSAA_SAMPLES,
                     ..Default::default()
                 },
+                label: "fsq-pipeline",
                 ..Default::default()
             }
         );
 
         self.fsq_bindings = SgBindings {
             vertex_buffers: vec!(quad_vbuf),
-            fs_images: vec![
-                self.offscreen_pass_desc.color_attachments[0].image,
-                self.offscreen_pass_desc.color_attachments[1].image,
-                self.offscreen_pass_desc.color_attachments[2].image,
-            ],
+            fs_images: self.offscreen_color_images.iter()
+                .map(Image::raw)
+                .collect(),
             ..Default::default()
         };
 
@@ -618,11 +625,13 @@ impl SApp for MRT {
                     ],
                     ..Default::default()
                 },
+                label: "dbg-shader",
             }),
             rasterizer: SgRasterizerState {
                 sample_count: MSAA_SAMPLES,
                 ..Default::default()
             },
+            label: "dbg-pipeline",
             ..Default::default()
         });
 
@@ -644,15 +653,32 @@ impl SApp for MRT {
         );
         let view_proj = proj * view;
 
-        self.rx += 1.0;
-        self.ry += 2.0;
-        let rxm = glm::rotation(self.rx * PI / 180.0, &glm::vec3(1.0, 0.0, 0.0));
-        let rym = glm::rotation(self.ry * PI / 180.0, &glm::vec3(0.0, 1.0, 0.0));
+        // 60 and 120 degrees per second
+        let (mut rx, mut ry) = (self.rx, self.ry);
+        let (mut prev_rx, mut prev_ry) = (self.prev_rx, self.prev_ry);
+        self.step.advance(|dt| {
+            prev_rx = rx;
+            prev_ry = ry;
+            rx += 60.0 * dt as f32;
+            ry += 120.0 * dt as f32;
+        });
+        self.rx = rx;
+        self.ry = ry;
+        self.prev_rx = prev_rx;
+        self.prev_ry = prev_ry;
+
+        // render in between the last two updates
+        let alpha = self.step.alpha() as f32;
+        let rx = prev_rx + (rx - prev_rx) * alpha;
+        let ry = prev_ry + (ry - prev_ry) * alpha;
+        let rxm = glm::rotation(rx * PI / 180.0, &glm::vec3(1.0, 0.0, 0.0));
+        let rym = glm::rotation(ry * PI / 180.0, &glm::vec3(0.0, 1.0, 0.0));
         let model = rxm * rym;
 
         let mvp: [[f32; 4]; 4] = (view_proj * model).into();
 
-        sg_begin_pass(self.offscreen_pass, &self.offscreen_pass_action);
+        let offscreen_pass = self.offscreen_pass.as_ref().unwrap();
+        sg_begin_pass(offscreen_pass.raw(), &self.offscreen_pass_action);
         sg_apply_pipeline(self.offscreen_pipeline);
         sg_apply_bindings(&self.offscreen_bindings);
         sg_apply_uniforms(SgShaderStage::Vertex, 0, &mvp, 64);
@@ -660,7 +686,7 @@ impl SApp for MRT {
         sg_end_pass();
 
         let offset: [f32; 2] = [
-            (self.rx * 0.01).sin() * 0.1, (self.ry * 0.01).sin() * 0.1
+            (rx * 0.01).sin() * 0.1, (ry * 0.01).sin() * 0.1
         ];
 
         sg_begin_default_pass(&self.default_pass_action, sapp_width(), sapp_height());
@@ -672,7 +698,7 @@ impl SApp for MRT {
         sg_apply_pipeline(self.dbg_pipeline);
         for i in 0..3 {
             sg_apply_viewport(i * 100, 0, 100, 100, false);
-            self.dbg_bindings.fs_images = vec![self.offscreen_pass_desc.color_attachments[i as usize].image];
+            self.dbg_bindings.fs_images = vec![self.offscreen_color_images[i as usize].raw()];
             sg_apply_bindings(&self.dbg_bindings);
             sg_draw(0, 4, 1);
         }
@@ -694,8 +720,9 @@ impl SApp for MRT {
 
 fn main() {
     let mrt_app = MRT {
-        offscreen_pass_desc: Default::default(),
-        offscreen_pass: Default::default(),
+        offscreen_color_images: Vec::new(),
+        offscreen_depth_image: None,
+        offscreen_pass: None,
         offscreen_pipeline: Default::default(),
         offscreen_bindings: Default::default(),
         fsq_pipeline: Default::default(),
@@ -722,8 +749,11 @@ fn main() {
         default_pass_action: SgPassAction {
             ..Default::default()
         },
+        step: FixedStep::default(),
         rx: 0.0,
         ry: 0.0,
+        prev_rx: 0.0,
+        prev_ry: 0.0,
     };
 
     let title = format!("mrt-sapp.rs ({:?})", sg_query_backend());
@@ -736,7 +766,7 @@ fn main() {
             sample_count: MSAA_SAMPLES,
             window_title: title,
             ..Default::default()
-        });
+        }).unwrap_or_else(|panic| panic.resume());
 
     std::process::exit(exit_code);
 }
@@ -10,6 +10,30 @@ use sokol_stb::vorbis::*;
 
 const NUM_SAMPLES: usize = 44800 * 2;
 
+//
+// only used in callback mode, if no audio file is given
+//
+struct SquareWave {
+    even_odd: u32,
+}
+
+impl SAudioStream for SquareWave {
+    fn saudio_stream(&mut self, buffer: &mut [f32], num_frames: i32, _num_channels: i32) {
+        let mut s: f32;
+        let mut sample_pos = 0;
+        for _i in 0..num_frames {
+            if (self.even_odd & (1 << 5)) != 0 {
+                s = 0.05;
+            } else {
+                s = -0.05;
+            }
+            self.even_odd += 1;
+            buffer[sample_pos as usize] = s;
+            sample_pos += 1;
+        }
+    }
+}
+
 struct SAudio {
     samples: Box<[f32; NUM_SAMPLES]>,
     audio_stream: Option<SAudioVorbis>,
@@ -32,7 +56,10 @@ impl SApp for SAudio {
         saudio_setup(SAudioDesc {
             sample_rate: 44800,
             num_channels: 2,
-            use_stream_cb: !self.audio_stream.is_some(),
+            stream_cb: match self.audio_stream {
+                None => Some(Box::new(SquareWave { even_odd: 0 })),
+                Some(_) => None,
+            },
             ..Default::default()
         });
     }
@@ -51,7 +78,7 @@ impl SApp for SAudio {
         sg_begin_default_pass(&pass_action, sapp_width(), sapp_height());
 
         //
-        // this block is only used if use_stream_cb = false (push mode)
+        // this block is only used if stream_cb = None (push mode)
         //
         match &mut self.audio_stream {
             None => {}
@@ -99,25 +126,6 @@ impl SApp for SAudio {
             }
         }
     }
-
-    fn saudio_stream(&mut self, buffer: &mut [f32], num_frames: i32, _num_channels: i32) {
-        //
-        // this function is only called if use_stream_cb = true (callback mode)
-        //
-        let mut s: f32;
-        let mut even_odd = 0;
-        let mut sample_pos = 0;
-        for _i in 0..num_frames {
-            if (even_odd & (1 << 5)) != 0 {
-                s = 0.05;
-            } else {
-                s = -0.05;
-            }
-            even_odd += 1;
-            buffer[sample_pos as usize] = s;
-            sample_pos += 1;
-        }
-    }
 }
 
 fn main() {
@@ -135,7 +143,7 @@ fn main() {
             height: 600,
             window_title: title,
             ..Default::default()
-        });
+        }).unwrap_or_else(|panic| panic.resume());
 
     std::process::exit(exit_code);
 }
@@ -11,32 +11,98 @@ fn build_new() -> (Build, Tool) {
     (build, tool)
 }
 
-fn select_sokol_gfx_renderer(build: &mut Build, is_msvc: bool, is_impl: bool) {
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum Backend {
+    D3D11,
+    Metal,
+    GLCore33,
+    GLES2,
+    GLES3,
+    Dummy,
+}
+
+impl Backend {
+    fn define(self) -> &'static str {
+        match self {
+            Backend::D3D11 => "-DSOKOL_D3D11",
+            Backend::Metal => "-DSOKOL_METAL",
+            Backend::GLCore33 => "-DSOKOL_GLCORE33",
+            Backend::GLES2 => "-DSOKOL_GLES2",
+            Backend::GLES3 => "-DSOKOL_GLES3",
+            Backend::Dummy => "-DSOKOL_DUMMY_BACKEND",
+        }
+    }
+
+    fn cfg_name(self) -> &'static str {
+        match self {
+            Backend::D3D11 => "d3d11",
+            Backend::Metal => "metal",
+            Backend::GLCore33 => "glcore33",
+            Backend::GLES2 => "gles2",
+            Backend::GLES3 => "gles3",
+            Backend::Dummy => "dummy",
+        }
+    }
+}
+
+fn default_sokol_gfx_backend(is_msvc: bool) -> Backend {
     //
-    // select sokol_gfx renderer, defaults to:
+    // "native" renderer, defaults to:
     // - Windows: D3D11 with MSVC, GLCORE33 otherwise
     // - MacOS: Metal
     // - Linux: GLCORE33
     //
     if cfg!(target_os = "windows") && is_msvc {
-        build.flag("-DSOKOL_D3D11");
+        Backend::D3D11
     } else if cfg!(target_os = "macos") {
-        build.flag("-DSOKOL_METAL");
+        Backend::Metal
     } else {
-        build.flag("-DSOKOL_GLCORE33");
+        Backend::GLCore33
     }
+}
 
-    if is_impl {
-        if cfg!(target_os = "windows") && is_msvc {
-            println!("cargo:rustc-cfg=gfx=\"d3d11\"");
-        } else if cfg!(target_os = "macos") {
-            println!("cargo:rustc-cfg=gfx=\"metal\"");
-        } else {
-            println!("cargo:rustc-cfg=gfx=\"glcore33\"");
-        }
+fn select_sokol_gfx_backend(is_msvc: bool) -> Backend {
+    //
+    // backend-* cargo features override the default renderer
+    //
+    let features = [
+        ("CARGO_FEATURE_BACKEND_D3D11", Backend::D3D11),
+        ("CARGO_FEATURE_BACKEND_METAL", Backend::Metal),
+        ("CARGO_FEATURE_BACKEND_GLCORE33", Backend::GLCore33),
+        ("CARGO_FEATURE_BACKEND_GLES2", Backend::GLES2),
+        ("CARGO_FEATURE_BACKEND_GLES3", Backend::GLES3),
+        ("CARGO_FEATURE_BACKEND_DUMMY", Backend::Dummy),
+    ];
+
+    let selected: Vec<Backend> = features.iter()
+        .filter(|(feature, _)| env::var_os(feature).is_some())
+        .map(|(_, backend)| *backend)
+        .collect();
+
+    match selected.len() {
+        0 => default_sokol_gfx_backend(is_msvc),
+        1 => selected[0],
+        _ => panic!("sokol-sys: only one backend-* feature can be enabled, found {:?}", selected),
     }
 }
 
+fn select_sokol_gfx_renderer(build: &mut Build, is_msvc: bool, is_impl: bool) -> Backend {
+    let backend = select_sokol_gfx_backend(is_msvc);
+
+    //
+    // the dummy backend doesn't compile sokol_app and sokol_audio, see
+    // sokol_rs_dummy.h, but sokol_imgui still selects its shaders by the
+    // native 3D API
+    //
+    if backend == Backend::Dummy && !is_impl {
+        build.flag(default_sokol_gfx_backend(is_msvc).define());
+    }
+
+    build.flag(backend.define());
+
+    backend
+}
+
 fn make_sokol() {
     let (mut build, tool) = build_new();
 
@@ -64,7 +130,10 @@ fn make_sokol() {
     //
     // select sokol_gfx renderer
     //
-    select_sokol_gfx_renderer(&mut build, is_msvc, true);
+    let backend = select_sokol_gfx_renderer(&mut build, is_msvc, true);
+    let is_dummy = backend == Backend::Dummy;
+
+    println!("cargo:rustc-cfg=gfx=\"{}\"", backend.cfg_name());
 
     //
     // silence some warnings
@@ -83,8 +152,10 @@ fn make_sokol() {
                 .flag_if_supported("-Wno-sign-compare")
                 .flag_if_supported("-Wno-unknown-pragmas");
 
-            println!("cargo:rustc-link-lib=static=gdi32");
-            println!("cargo:rustc-link-lib=static=ole32");
+            if !is_dummy {
+                println!("cargo:rustc-link-lib=static=gdi32");
+                println!("cargo:rustc-link-lib=static=ole32");
+            }
         }
     }
 
@@ -94,13 +165,21 @@ fn make_sokol() {
             .flag("-DSOKOL_DEBUG");
     }
 
+    //
+    // validation errors are reported to Rust instead of terminating the program
+    //
+    if env::var_os("CARGO_FEATURE_VALIDATE_NON_FATAL").is_some() {
+        build
+            .flag("-DSOKOL_VALIDATE_NON_FATAL");
+    }
+
     build
         .compile("sokol-sys");
 
     //
     // MacOS: frameworks
     //
-    if cfg!(target_os = "macos") {
+    if cfg!(target_os = "macos") && !is_dummy {
         println!("cargo:rustc-link-lib=framework=Cocoa");
         println!("cargo:rustc-link-lib=framework=QuartzCore");
         println!("cargo:rustc-link-lib=framework=Metal");
@@ -111,7 +190,7 @@ fn make_sokol() {
     //
     // Linux: libs
     //
-    if cfg!(target_os = "linux") {
+    if cfg!(target_os = "linux") && !is_dummy {
         println!("cargo:rustc-link-lib=dylib=GL");
         println!("cargo:rustc-link-lib=dylib=X11");
         println!("cargo:rustc-link-lib=dylib=asound");
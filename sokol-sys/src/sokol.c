@@ -2,7 +2,18 @@
 #define SOKOL_NO_ENTRY
 #define SOKOL_NO_DEPRECATED
 #define SOKOL_TRACE_HOOKS
+#include "sokol_rs_log.h"
+#if defined(SOKOL_DUMMY_BACKEND)
+/* declarations only, implemented by sokol_rs_dummy.h */
+#undef SOKOL_IMPL
 #include <sokol_app.h>
 #include <sokol_audio.h>
+#define SOKOL_IMPL
+#include "sokol_rs_dummy.h"
+#else
+#include <sokol_app.h>
+#include <sokol_audio.h>
+#endif
 #include <sokol_gfx.h>
 #include <sokol_time.h>
+#include "sokol_rs.h"
@@ -0,0 +1,421 @@
+/*
+    sokol-rs: extensions
+
+    Included after the sokol implementations. Provides access to some
+    internal state which isn't exposed by the public sokol API.
+*/
+
+/* returns the error of the last sokol_gfx validation, or NULL */
+const char* sokol_rs_sg_validate_error(void) {
+#if defined(SOKOL_DEBUG)
+    if (_sg.validate_error != _SG_VALIDATE_SUCCESS) {
+        return _sg_validate_string(_sg.validate_error);
+    }
+#endif
+    return 0;
+}
+
+/* window system used by sokol_app, none with the dummy backend */
+#if defined(SOKOL_DUMMY_BACKEND)
+#elif defined(__APPLE__) && defined(__OBJC__)
+    #if TARGET_OS_IPHONE
+        #define SOKOL_RS_IOS
+    #else
+        #define SOKOL_RS_MACOS
+    #endif
+#elif defined(_WIN32)
+    #define SOKOL_RS_WIN32
+#elif (defined(__linux__) || defined(__unix__)) && !defined(__EMSCRIPTEN__) && !defined(__ANDROID__)
+    #define SOKOL_RS_X11
+#endif
+
+/* platform handles which sokol_app doesn't expose */
+#if defined(SOKOL_RS_IOS)
+const void* sokol_rs_ios_get_view_controller(void) {
+    UIWindow* window = (__bridge UIWindow*) sapp_ios_get_window();
+    return (__bridge const void*) window.rootViewController;
+}
+
+const void* sokol_rs_ios_get_view(void) {
+    UIWindow* window = (__bridge UIWindow*) sapp_ios_get_window();
+    return (__bridge const void*) window.rootViewController.view;
+}
+#else
+const void* sokol_rs_ios_get_view_controller(void) {
+    return 0;
+}
+
+const void* sokol_rs_ios_get_view(void) {
+    return 0;
+}
+#endif
+
+#if defined(SOKOL_RS_MACOS)
+const void* sokol_rs_macos_get_view(void) {
+    NSWindow* window = (__bridge NSWindow*) sapp_macos_get_window();
+    return (__bridge const void*) window.contentView;
+}
+#else
+const void* sokol_rs_macos_get_view(void) {
+    return 0;
+}
+#endif
+
+#if defined(SOKOL_RS_WIN32)
+const void* sokol_rs_win32_get_hinstance(void) {
+    return (const void*) GetModuleHandleW(NULL);
+}
+#else
+const void* sokol_rs_win32_get_hinstance(void) {
+    return 0;
+}
+#endif
+
+#if defined(SOKOL_RS_X11)
+void* sokol_rs_x11_get_display(void) {
+    return (void*) _sapp_x11_display;
+}
+
+unsigned long sokol_rs_x11_get_window(void) {
+    return (unsigned long) _sapp_x11_window;
+}
+
+int sokol_rs_x11_get_screen(void) {
+    return _sapp_x11_display ? DefaultScreen(_sapp_x11_display) : 0;
+}
+
+const void* sokol_rs_glx_get_context(void) {
+    return (const void*) _sapp_glx_ctx;
+}
+#else
+void* sokol_rs_x11_get_display(void) {
+    return 0;
+}
+
+unsigned long sokol_rs_x11_get_window(void) {
+    return 0;
+}
+
+int sokol_rs_x11_get_screen(void) {
+    return 0;
+}
+
+const void* sokol_rs_glx_get_context(void) {
+    return 0;
+}
+#endif
+
+/* mouse cursor, pointer lock and relative mouse movement */
+enum {
+    SOKOL_RS_MOUSECURSOR_DEFAULT,
+    SOKOL_RS_MOUSECURSOR_ARROW,
+    SOKOL_RS_MOUSECURSOR_IBEAM,
+    SOKOL_RS_MOUSECURSOR_CROSSHAIR,
+    SOKOL_RS_MOUSECURSOR_POINTING_HAND,
+    SOKOL_RS_MOUSECURSOR_RESIZE_EW,
+    SOKOL_RS_MOUSECURSOR_RESIZE_NS,
+    SOKOL_RS_MOUSECURSOR_RESIZE_ALL,
+    SOKOL_RS_MOUSECURSOR_NOT_ALLOWED,
+    SOKOL_RS_MOUSECURSOR_NUM,
+};
+
+static struct {
+    bool hidden;
+    bool locked;
+    int cursor;
+    bool pos_valid;
+    float pos_x;
+    float pos_y;
+} _sokol_rs_mouse;
+
+#if defined(SOKOL_RS_X11)
+#include <X11/cursorfont.h>
+
+static Cursor _sokol_rs_x11_hidden_cursor;
+static Cursor _sokol_rs_x11_cursors[SOKOL_RS_MOUSECURSOR_NUM];
+
+/* the pointer grab of a locked mouse, released while the window is iconified or unfocused */
+static struct {
+    bool grabbed;
+    bool iconified;
+    bool unfocused;
+    int focus_poll_frames;
+    bool warp_pending;
+    int warp_x;
+    int warp_y;
+} _sokol_rs_x11_grab;
+
+static const unsigned int _sokol_rs_x11_cursor_shapes[SOKOL_RS_MOUSECURSOR_NUM] = {
+    XC_left_ptr,
+    XC_left_ptr,
+    XC_xterm,
+    XC_crosshair,
+    XC_hand2,
+    XC_sb_h_double_arrow,
+    XC_sb_v_double_arrow,
+    XC_fleur,
+    XC_X_cursor,
+};
+
+static Cursor _sokol_rs_x11_get_hidden_cursor(void) {
+    if (!_sokol_rs_x11_hidden_cursor) {
+        char data[1] = { 0 };
+        XColor color;
+        memset(&color, 0, sizeof(color));
+        Pixmap pixmap = XCreateBitmapFromData(_sapp_x11_display, _sapp_x11_window, data, 1, 1);
+        _sokol_rs_x11_hidden_cursor = XCreatePixmapCursor(_sapp_x11_display, pixmap, pixmap, &color, &color, 0, 0);
+        XFreePixmap(_sapp_x11_display, pixmap);
+    }
+    return _sokol_rs_x11_hidden_cursor;
+}
+
+static void _sokol_rs_x11_update_cursor(void) {
+    if (!_sapp_x11_display || !_sapp_x11_window) {
+        return;
+    }
+    if (_sokol_rs_mouse.hidden || _sokol_rs_x11_grab.grabbed) {
+        XDefineCursor(_sapp_x11_display, _sapp_x11_window, _sokol_rs_x11_get_hidden_cursor());
+    }
+    else if (_sokol_rs_mouse.cursor == SOKOL_RS_MOUSECURSOR_DEFAULT) {
+        XUndefineCursor(_sapp_x11_display, _sapp_x11_window);
+    }
+    else {
+        Cursor* cursor = &_sokol_rs_x11_cursors[_sokol_rs_mouse.cursor];
+        if (!*cursor) {
+            *cursor = XCreateFontCursor(_sapp_x11_display, _sokol_rs_x11_cursor_shapes[_sokol_rs_mouse.cursor]);
+        }
+        XDefineCursor(_sapp_x11_display, _sapp_x11_window, *cursor);
+    }
+    XFlush(_sapp_x11_display);
+}
+
+/*
+    moves the pointer to the window center, which becomes the reference for
+    the next delta once the motion event of the warp arrives
+*/
+static void _sokol_rs_x11_center_pointer(void) {
+    int x = _sapp.window_width / 2;
+    int y = _sapp.window_height / 2;
+    if (_sokol_rs_mouse.pos_valid && _sokol_rs_mouse.pos_x == (float) x && _sokol_rs_mouse.pos_y == (float) y) {
+        return;
+    }
+    XWarpPointer(_sapp_x11_display, None, _sapp_x11_window, 0, 0, 0, 0, x, y);
+    _sokol_rs_x11_grab.warp_pending = true;
+    _sokol_rs_x11_grab.warp_x = x;
+    _sokol_rs_x11_grab.warp_y = y;
+}
+
+static void _sokol_rs_x11_grab_pointer(void) {
+    if (_sokol_rs_x11_grab.grabbed) {
+        return;
+    }
+    int result = XGrabPointer(_sapp_x11_display, _sapp_x11_window, True,
+        ButtonPressMask | ButtonReleaseMask | PointerMotionMask,
+        GrabModeAsync, GrabModeAsync, _sapp_x11_window,
+        _sokol_rs_x11_get_hidden_cursor(), CurrentTime);
+    if (result != GrabSuccess) {
+        return;
+    }
+    _sokol_rs_x11_grab.grabbed = true;
+
+    /* the pointer may have moved outside of the window since the last event */
+    Window root, child;
+    int root_x, root_y, x, y;
+    unsigned int mask;
+    if (XQueryPointer(_sapp_x11_display, _sapp_x11_window, &root, &child, &root_x, &root_y, &x, &y, &mask)) {
+        _sokol_rs_mouse.pos_valid = true;
+        _sokol_rs_mouse.pos_x = (float) x;
+        _sokol_rs_mouse.pos_y = (float) y;
+    }
+    _sokol_rs_x11_center_pointer();
+}
+
+static void _sokol_rs_x11_ungrab_pointer(void) {
+    if (!_sokol_rs_x11_grab.grabbed) {
+        return;
+    }
+    XUngrabPointer(_sapp_x11_display, CurrentTime);
+    _sokol_rs_x11_grab.grabbed = false;
+    _sokol_rs_x11_grab.warp_pending = false;
+}
+
+/*
+    grabs the pointer of a locked mouse while the window is active, and
+    releases it otherwise; a failed grab is retried on the next update, e.g.
+    while the window manager still holds its own grab
+*/
+static void _sokol_rs_x11_update_grab(void) {
+    if (!_sokol_rs_mouse.locked) {
+        return;
+    }
+    bool grabbed = _sokol_rs_x11_grab.grabbed;
+    if (_sokol_rs_x11_grab.iconified || _sokol_rs_x11_grab.unfocused) {
+        _sokol_rs_x11_ungrab_pointer();
+    }
+    else {
+        _sokol_rs_x11_grab_pointer();
+    }
+    if (grabbed != _sokol_rs_x11_grab.grabbed) {
+        _sokol_rs_x11_update_cursor();
+    }
+}
+
+void sokol_rs_show_mouse(bool shown) {
+    _sokol_rs_mouse.hidden = !shown;
+    _sokol_rs_x11_update_cursor();
+}
+
+void sokol_rs_set_mouse_cursor(int cursor) {
+    if (cursor >= 0 && cursor < SOKOL_RS_MOUSECURSOR_NUM) {
+        _sokol_rs_mouse.cursor = cursor;
+        _sokol_rs_x11_update_cursor();
+    }
+}
+
+void sokol_rs_lock_mouse(bool locked) {
+    if (locked == _sokol_rs_mouse.locked || !_sapp_x11_display || !_sapp_x11_window) {
+        return;
+    }
+    if (locked) {
+        _sokol_rs_x11_grab_pointer();
+        if (!_sokol_rs_x11_grab.grabbed) {
+            return;
+        }
+        _sokol_rs_mouse.locked = true;
+        _sokol_rs_x11_grab.unfocused = false;
+        _sokol_rs_x11_grab.focus_poll_frames = 0;
+    }
+    else {
+        _sokol_rs_x11_ungrab_pointer();
+        _sokol_rs_mouse.locked = false;
+    }
+    _sokol_rs_x11_update_cursor();
+}
+
+/*
+    called before each frame: the focus events are consumed by sokol_app, so
+    the focus is polled while the mouse is locked, only every few frames
+    because XGetInputFocus() is a round trip
+*/
+#define SOKOL_RS_X11_FOCUS_POLL_FRAMES 30
+
+void sokol_rs_mouse_frame(void) {
+    if (!_sokol_rs_mouse.locked || !_sapp_x11_display || !_sapp_x11_window) {
+        return;
+    }
+    if (++_sokol_rs_x11_grab.focus_poll_frames >= SOKOL_RS_X11_FOCUS_POLL_FRAMES) {
+        Window focus;
+        int revert_to;
+        XGetInputFocus(_sapp_x11_display, &focus, &revert_to);
+        _sokol_rs_x11_grab.unfocused = (focus != _sapp_x11_window);
+        _sokol_rs_x11_grab.focus_poll_frames = 0;
+    }
+    _sokol_rs_x11_update_grab();
+}
+
+/* called after the cleanup callback, while the window still exists */
+void sokol_rs_mouse_shutdown(void) {
+    if (!_sapp_x11_display) {
+        return;
+    }
+    _sokol_rs_x11_ungrab_pointer();
+    _sokol_rs_mouse.locked = false;
+    if (_sokol_rs_x11_hidden_cursor) {
+        XFreeCursor(_sapp_x11_display, _sokol_rs_x11_hidden_cursor);
+        _sokol_rs_x11_hidden_cursor = 0;
+    }
+    for (int i = 0; i < SOKOL_RS_MOUSECURSOR_NUM; i++) {
+        if (_sokol_rs_x11_cursors[i]) {
+            XFreeCursor(_sapp_x11_display, _sokol_rs_x11_cursors[i]);
+            _sokol_rs_x11_cursors[i] = 0;
+        }
+    }
+    XFlush(_sapp_x11_display);
+}
+#else
+void sokol_rs_show_mouse(bool shown) {
+    (void) shown;
+}
+
+void sokol_rs_set_mouse_cursor(int cursor) {
+    (void) cursor;
+}
+
+void sokol_rs_lock_mouse(bool locked) {
+    (void) locked;
+}
+
+void sokol_rs_mouse_frame(void) {
+}
+
+void sokol_rs_mouse_shutdown(void) {
+}
+#endif
+
+bool sokol_rs_mouse_shown(void) {
+    return !_sokol_rs_mouse.hidden;
+}
+
+int sokol_rs_get_mouse_cursor(void) {
+    return _sokol_rs_mouse.cursor;
+}
+
+bool sokol_rs_mouse_locked(void) {
+    return _sokol_rs_mouse.locked;
+}
+
+/*
+    updates the pointer state from an event, and computes the mouse movement
+    since the last mouse move event
+*/
+void sokol_rs_mouse_event(const sapp_event* e, float* dx, float* dy) {
+    *dx = 0.0f;
+    *dy = 0.0f;
+#if defined(SOKOL_RS_X11)
+    switch (e->type) {
+        case SAPP_EVENTTYPE_ICONIFIED:
+        case SAPP_EVENTTYPE_SUSPENDED:
+            _sokol_rs_x11_grab.iconified = true;
+            _sokol_rs_x11_update_grab();
+            break;
+        case SAPP_EVENTTYPE_RESTORED:
+        case SAPP_EVENTTYPE_RESUMED:
+            _sokol_rs_x11_grab.iconified = false;
+            _sokol_rs_x11_update_grab();
+            break;
+        default:
+            break;
+    }
+#endif
+    if (e->type == SAPP_EVENTTYPE_MOUSE_ENTER) {
+        _sokol_rs_mouse.pos_valid = false;
+    }
+    if (e->type != SAPP_EVENTTYPE_MOUSE_MOVE) {
+        return;
+    }
+#if defined(SOKOL_RS_X11)
+    /* motion from before the warp is relative to the old position, and would jitter */
+    if (_sokol_rs_x11_grab.warp_pending) {
+        if (e->mouse_x != (float) _sokol_rs_x11_grab.warp_x || e->mouse_y != (float) _sokol_rs_x11_grab.warp_y) {
+            return;
+        }
+        _sokol_rs_x11_grab.warp_pending = false;
+        _sokol_rs_mouse.pos_valid = true;
+        _sokol_rs_mouse.pos_x = e->mouse_x;
+        _sokol_rs_mouse.pos_y = e->mouse_y;
+        return;
+    }
+#endif
+    if (_sokol_rs_mouse.pos_valid) {
+        *dx = e->mouse_x - _sokol_rs_mouse.pos_x;
+        *dy = e->mouse_y - _sokol_rs_mouse.pos_y;
+    }
+    _sokol_rs_mouse.pos_valid = true;
+    _sokol_rs_mouse.pos_x = e->mouse_x;
+    _sokol_rs_mouse.pos_y = e->mouse_y;
+#if defined(SOKOL_RS_X11)
+    if (_sokol_rs_x11_grab.grabbed && (*dx != 0.0f || *dy != 0.0f)) {
+        _sokol_rs_x11_center_pointer();
+    }
+#endif
+}
@@ -0,0 +1,139 @@
+/*
+    sokol-rs: dummy backend
+
+    Included instead of the sokol_app and sokol_audio implementations if
+    compiled with SOKOL_DUMMY_BACKEND, so that no window system, 3D API or
+    audio libraries need to be linked. sapp_run() fails right away, and all
+    other functions report an invalid application and audio device.
+*/
+int sapp_run(const sapp_desc* desc) {
+    if (desc->fail_userdata_cb) {
+        desc->fail_userdata_cb("sokol_app is not available with the dummy backend\n", desc->user_data);
+    }
+    else if (desc->fail_cb) {
+        desc->fail_cb("sokol_app is not available with the dummy backend\n");
+    }
+    return 1;
+}
+
+bool sapp_isvalid(void) {
+    return false;
+}
+
+int sapp_width(void) {
+    return 0;
+}
+
+int sapp_height(void) {
+    return 0;
+}
+
+bool sapp_high_dpi(void) {
+    return false;
+}
+
+float sapp_dpi_scale(void) {
+    return 1.0f;
+}
+
+void sapp_show_keyboard(bool visible) {
+    (void) visible;
+}
+
+bool sapp_keyboard_shown(void) {
+    return false;
+}
+
+void* sapp_userdata(void) {
+    return 0;
+}
+
+void sapp_request_quit(void) {
+}
+
+void sapp_cancel_quit(void) {
+}
+
+void sapp_quit(void) {
+}
+
+uint64_t sapp_frame_count(void) {
+    return 0;
+}
+
+bool sapp_gles2(void) {
+    return false;
+}
+
+const void* sapp_metal_get_device(void) {
+    return 0;
+}
+
+const void* sapp_metal_get_renderpass_descriptor(void) {
+    return 0;
+}
+
+const void* sapp_metal_get_drawable(void) {
+    return 0;
+}
+
+const void* sapp_macos_get_window(void) {
+    return 0;
+}
+
+const void* sapp_ios_get_window(void) {
+    return 0;
+}
+
+const void* sapp_d3d11_get_device(void) {
+    return 0;
+}
+
+const void* sapp_d3d11_get_device_context(void) {
+    return 0;
+}
+
+const void* sapp_d3d11_get_render_target_view(void) {
+    return 0;
+}
+
+const void* sapp_d3d11_get_depth_stencil_view(void) {
+    return 0;
+}
+
+const void* sapp_win32_get_hwnd(void) {
+    return 0;
+}
+
+void saudio_setup(const saudio_desc* desc) {
+    (void) desc;
+}
+
+void saudio_shutdown(void) {
+}
+
+bool saudio_isvalid(void) {
+    return false;
+}
+
+void* saudio_userdata(void) {
+    return 0;
+}
+
+int saudio_sample_rate(void) {
+    return 0;
+}
+
+int saudio_channels(void) {
+    return 0;
+}
+
+int saudio_expect(void) {
+    return 0;
+}
+
+int saudio_push(const float* frames, int num_frames) {
+    (void) frames;
+    (void) num_frames;
+    return 0;
+}
@@ -0,0 +1,43 @@
+/*
+    sokol-rs: log capture
+
+    Included before the sokol headers. Replaces SOKOL_LOG with a function which
+    keeps the messages logged since the last call to sokol_rs_log_clear(), so
+    that the Rust side can report them, e.g. shader compile errors.
+*/
+#include <stdbool.h>
+#include <stdio.h>
+#include <string.h>
+
+#define SOKOL_RS_LOG_SIZE (16 * 1024)
+
+static char sokol_rs_log_buf[SOKOL_RS_LOG_SIZE];
+static size_t sokol_rs_log_len = 0;
+
+static void sokol_rs_log(const char* msg) {
+#if defined(SOKOL_DEBUG)
+    puts(msg);
+#endif
+    size_t len = strlen(msg);
+    size_t avail = SOKOL_RS_LOG_SIZE - sokol_rs_log_len - 1;
+    if (avail > 0) {
+        if (len >= avail) {
+            len = avail - 1;
+        }
+        memcpy(sokol_rs_log_buf + sokol_rs_log_len, msg, len);
+        sokol_rs_log_len += len;
+        sokol_rs_log_buf[sokol_rs_log_len++] = '\n';
+        sokol_rs_log_buf[sokol_rs_log_len] = 0;
+    }
+}
+
+void sokol_rs_log_clear(void) {
+    sokol_rs_log_len = 0;
+    sokol_rs_log_buf[0] = 0;
+}
+
+const char* sokol_rs_log_get(void) {
+    return sokol_rs_log_buf;
+}
+
+#define SOKOL_LOG(s) sokol_rs_log(s)
@@ -3,20 +3,56 @@
 //! A Rust API to the [sokol_app.h](https://github.com/floooh/sokol/blob/master/sokol_app.h)
 //! header-only C library.
 
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::os::raw::c_int;
+use std::os::raw::c_ulong;
 use std::os::raw::c_void;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+
+use crate::marshal::MarshalArena;
+
+pub use self::actions::*;
+pub use self::builder::*;
+pub use self::config::*;
+pub use self::event::*;
+pub use self::gesture::*;
+pub use self::headless::*;
+pub use self::input::*;
+pub use self::panic::*;
+pub use self::platform::*;
+pub use self::record::*;
+pub use self::state::*;
+pub use self::text_input::*;
+
+mod actions;
+mod builder;
+mod config;
+mod event;
+mod gesture;
+mod headless;
+mod input;
+mod panic;
+mod platform;
+mod record;
+mod state;
+mod text_input;
 
 pub mod ffi {
     use std::ffi::CStr;
-    use std::ffi::CString;
     use std::os::raw::c_char;
     use std::os::raw::c_int;
+    use std::os::raw::c_ulong;
     use std::os::raw::c_void;
     use std::ptr::null;
-    use std::slice::from_raw_parts_mut;
+
+    use crate::marshal::MarshalArena;
 
     pub const SAPP_MAX_TOUCHPOINTS: usize = 8;
     pub const SAPP_MAX_MOUSEBUTTONS: usize = 3;
-    const _SAPP_MAX_KEYCODES: usize = 512;
+    pub const SAPP_MAX_KEYCODES: usize = 512;
 
     #[repr(C)]
     #[derive(Copy, Clone, Debug)]
@@ -129,15 +165,31 @@ pub mod ffi {
         pub fn sapp_d3d11_get_render_target_view() -> *const c_void;
         pub fn sapp_d3d11_get_depth_stencil_view() -> *const c_void;
         pub fn sapp_win32_get_hwnd() -> *const c_void;
-    }
 
-    pub fn sapp_make_desc(app: &super::SAppImpl) -> SAppDesc {
+        pub fn sokol_rs_macos_get_view() -> *const c_void;
+        pub fn sokol_rs_ios_get_view() -> *const c_void;
+        pub fn sokol_rs_ios_get_view_controller() -> *const c_void;
+        pub fn sokol_rs_win32_get_hinstance() -> *const c_void;
+        pub fn sokol_rs_x11_get_display() -> *mut c_void;
+        pub fn sokol_rs_x11_get_window() -> c_ulong;
+        pub fn sokol_rs_x11_get_screen() -> c_int;
+        pub fn sokol_rs_glx_get_context() -> *const c_void;
+
+        pub fn sokol_rs_show_mouse(shown: bool);
+        pub fn sokol_rs_mouse_shown() -> bool;
+        pub fn sokol_rs_set_mouse_cursor(cursor: super::SAppMouseCursor);
+        pub fn sokol_rs_get_mouse_cursor() -> super::SAppMouseCursor;
+        pub fn sokol_rs_lock_mouse(locked: bool);
+        pub fn sokol_rs_mouse_locked() -> bool;
+        pub fn sokol_rs_mouse_event(event: *const SAppEvent, dx: *mut f32, dy: *mut f32);
+        pub fn sokol_rs_mouse_frame();
+        pub fn sokol_rs_mouse_shutdown();
+    }
+
+    pub(crate) fn sapp_make_desc(app: &super::SAppImpl, arena: &mut MarshalArena) -> SAppDesc {
         let app_ptr = app as *const super::SAppImpl;
         let desc = &app.desc;
 
-        let window_title = CString::new(&*desc.window_title).unwrap();
-        let canvas_name = CString::new(&*desc.html5_canvas_name).unwrap();
-
         SAppDesc {
             init_cb: null(),
             frame_cb: null(),
@@ -159,10 +211,10 @@ pub mod ffi {
             high_dpi: desc.high_dpi,
             fullscreen: desc.fullscreen,
             alpha: desc.alpha,
-            window_title: window_title.into_raw(),
+            window_title: arena.c_str(&desc.window_title),
             user_cursor: desc.user_cursor,
 
-            html5_canvas_name: canvas_name.into_raw(),
+            html5_canvas_name: arena.c_str(&desc.html5_canvas_name),
             html5_canvas_resize: desc.html5_canvas_resize,
             html5_preserve_drawing_buffer: desc.html5_preserve_drawing_buffer,
             html5_premultiplied_alpha: desc.html5_premultiplied_alpha,
@@ -172,27 +224,35 @@ pub mod ffi {
         }
     }
 
-    #[no_mangle]
-    pub extern fn init_userdata_cb(user_data: *mut c_void) {
+    extern fn init_userdata_cb(user_data: *mut c_void) {
         super::SAppImpl::get(user_data).init_cb();
     }
 
-    #[no_mangle]
-    pub extern fn frame_userdata_cb(user_data: *mut c_void) {
+    extern fn frame_userdata_cb(user_data: *mut c_void) {
+        unsafe {
+            sokol_rs_mouse_frame();
+        }
         super::SAppImpl::get(user_data).frame_cb();
     }
 
-    #[no_mangle]
-    pub extern fn cleanup_userdata_cb(user_data: *mut c_void) {
+    extern fn cleanup_userdata_cb(user_data: *mut c_void) {
         super::SAppImpl::get(user_data).cleanup_cb();
+        unsafe {
+            sokol_rs_mouse_shutdown();
+        }
     }
 
-    #[no_mangle]
-    pub extern fn event_userdata_cb(event: *const SAppEvent, user_data: *mut c_void) {
+    extern fn event_userdata_cb(event: *const SAppEvent, user_data: *mut c_void) {
         let e = *unsafe {
             &*event
         };
 
+        let mut mouse_dx = 0.0;
+        let mut mouse_dy = 0.0;
+        unsafe {
+            sokol_rs_mouse_event(event, &mut mouse_dx, &mut mouse_dy);
+        }
+
         super::SAppImpl::get(user_data).event_cb(super::SAppEvent {
             frame_count: e.frame_count,
             event_type: e.event_type,
@@ -203,6 +263,8 @@ pub mod ffi {
             mouse_button: e.mouse_button,
             mouse_x: e.mouse_x,
             mouse_y: e.mouse_y,
+            mouse_dx,
+            mouse_dy,
             scroll_x: e.scroll_x,
             scroll_y: e.scroll_y,
             num_touches: e.num_touches,
@@ -214,23 +276,12 @@ pub mod ffi {
         });
     }
 
-    #[no_mangle]
-    pub extern fn fail_userdata_cb(message: *const c_char, user_data: *mut c_void) {
+    extern fn fail_userdata_cb(message: *const c_char, user_data: *mut c_void) {
         let msg = unsafe {
             CStr::from_ptr(message)
         };
 
-        super::SAppImpl::get(user_data).fail_cb(msg.to_str().unwrap());
-    }
-
-    #[no_mangle]
-    pub extern fn stream_userdata_cb(buffer: *mut f32, num_frames: c_int, num_channels: c_int, user_data: *mut c_void) {
-        let arr = unsafe {
-            let len = num_frames * num_channels;
-            from_raw_parts_mut(buffer, len as usize)
-        };
-
-        super::SAppImpl::get(user_data).stream_cb(arr, num_frames, num_channels);
+        super::SAppImpl::get(user_data).fail_cb(&msg.to_string_lossy());
     }
 }
 
@@ -395,6 +446,22 @@ pub enum SAppMouseButton {
     Middle = 2,
 }
 
+/// Standard mouse cursor shapes, see `sapp_set_mouse_cursor()`.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SAppMouseCursor {
+    /// The default cursor of the window system.
+    Default,
+    Arrow,
+    IBeam,
+    Crosshair,
+    PointingHand,
+    ResizeEW,
+    ResizeNS,
+    ResizeAll,
+    NotAllowed,
+}
+
 bitflags! {
     #[repr(C)]
     pub struct SAppModifier: u32 {
@@ -425,6 +492,10 @@ pub struct SAppEvent {
     pub mouse_button: SAppMouseButton,
     pub mouse_x: f32,
     pub mouse_y: f32,
+    /// Mouse movement since the last `MouseMove` event. Unlike the mouse
+    /// position, this keeps changing while the pointer is locked.
+    pub mouse_dx: f32,
+    pub mouse_dy: f32,
     pub scroll_x: f32,
     pub scroll_y: f32,
     pub num_touches: i32,
@@ -473,136 +544,351 @@ pub trait SApp {
     fn sapp_fail(&mut self, msg: &str) {
         print!("{}", msg);
     }
-
-    /// Function called by `sokol_audio` in callback mode.
-    ///
-    /// The default implementation clears the buffer to zero. Applications
-    /// using this mode are expected to mix audio data into the buffer.
-    ///
-    /// This is called from a separate thread on all desktop platforms.
-    fn saudio_stream(&mut self, buffer: &mut [f32], num_frames: i32, num_channels: i32) {
-        let len = (num_frames * num_channels) as usize;
-        for i in 0..len {
-            buffer[i] = 0.0;
-        }
-    }
 }
 
-pub struct SAppImpl {
-    callbacks: Box<SApp>,
+pub(crate) type PanicPayload = Box<dyn Any + Send + 'static>;
+
+type PendingCallback = Box<dyn FnOnce(&mut dyn SApp)>;
+
+/// The application, as seen by the sokol_app callbacks.
+///
+/// The callbacks only ever get a shared reference. The application is
+/// borrowed mutably for the duration of each callback.
+pub(crate) struct SAppImpl {
+    callbacks: RefCell<Box<dyn SApp>>,
     desc: SAppDesc,
+    panic: RefCell<Option<PanicPayload>>,
+    pending: RefCell<VecDeque<PendingCallback>>,
 }
 
 impl SAppImpl {
     fn new<S: SApp + 'static>(callbacks: S, desc: SAppDesc) -> SAppImpl {
         SAppImpl {
-            callbacks: Box::new(callbacks),
+            callbacks: RefCell::new(Box::new(callbacks)),
             desc,
+            panic: RefCell::new(None),
+            pending: RefCell::new(VecDeque::new()),
         }
     }
 
-    pub fn init_cb(&mut self) {
-        self.callbacks.sapp_init();
+    fn has_panicked(&self) -> bool {
+        self.panic.borrow().is_some()
     }
 
-    pub fn frame_cb(&mut self) {
-        self.callbacks.sapp_frame();
+    /// Stores the payload of the first panic.
+    fn store_panic(&self, payload: PanicPayload) {
+        let mut panic = self.panic.borrow_mut();
+        if panic.is_none() {
+            *panic = Some(payload);
+        }
     }
 
-    pub fn cleanup_cb(&mut self) {
-        self.callbacks.sapp_cleanup();
+    fn take_panic(&self) -> Option<PanicPayload> {
+        self.panic.borrow_mut().take()
     }
 
-    pub fn event_cb(&mut self, event: SAppEvent) {
-        self.callbacks.sapp_event(event);
+    /// Calls into the application, unless an earlier callback panicked.
+    fn call<F: FnOnce(&mut dyn SApp) + 'static>(&self, f: F) {
+        if self.has_panicked() {
+            return;
+        }
+        self.call_always(f);
     }
 
-    pub fn fail_cb(&mut self, msg: &str) {
-        self.callbacks.sapp_fail(msg);
+    /// Calls into the application. A panic is stored, and sokol_app is
+    /// requested to quit.
+    ///
+    /// Callbacks issued by sokol_app while the application is already
+    /// borrowed, i.e. from within another callback, are queued, and called
+    /// in order after the outer callback returned. They are dropped if a
+    /// callback panics.
+    fn call_always<F: FnOnce(&mut dyn SApp) + 'static>(&self, f: F) {
+        let mut callbacks = match self.callbacks.try_borrow_mut() {
+            Ok(callbacks) => callbacks,
+            Err(_) => {
+                self.pending.borrow_mut().push_back(Box::new(f));
+                return;
+            }
+        };
+
+        if !self.call_catch_unwind(&mut **callbacks, f) {
+            return;
+        }
+
+        loop {
+            let f = match self.pending.borrow_mut().pop_front() {
+                Some(f) => f,
+                None => break,
+            };
+            if !self.call_catch_unwind(&mut **callbacks, f) {
+                return;
+            }
+        }
+    }
+
+    /// Returns false if the callback panicked.
+    fn call_catch_unwind<F: FnOnce(&mut dyn SApp)>(&self, callbacks: &mut dyn SApp, f: F) -> bool {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            f(callbacks)
+        })).and_then(|_| {
+            // trace hooks called by sokol_gfx can't unwind themselves
+            match crate::gfx::take_trace_hooks_panic() {
+                Some(payload) => Err(payload),
+                None => Ok(()),
+            }
+        });
+
+        match result {
+            Ok(()) => true,
+            Err(payload) => {
+                self.pending.borrow_mut().clear();
+                self.store_panic(payload);
+                sapp_quit();
+                false
+            }
+        }
     }
 
-    pub fn stream_cb(&mut self, buffer: &mut [f32], num_frames: i32, num_channels: i32) {
-        self.callbacks.saudio_stream(buffer, num_frames, num_channels);
+    fn init_cb(&self) {
+        self.call(|cb| cb.sapp_init());
     }
 
-    pub fn get(user_data: *mut c_void) -> &'static mut SAppImpl {
+    fn frame_cb(&self) {
+        if crate::audio::saudio_stream_panicked() {
+            // the audio thread can't request to quit
+            sapp_quit();
+            return;
+        }
+        self.call(|cb| cb.sapp_frame());
+    }
+
+    fn cleanup_cb(&self) {
+        self.call_always(|cb| cb.sapp_cleanup());
+    }
+
+    fn event_cb(&self, event: SAppEvent) {
+        self.call(|cb| cb.sapp_event(event));
+    }
+
+    fn fail_cb(&self, msg: &str) {
+        let msg = msg.to_string();
+        self.call(move |cb| cb.sapp_fail(&msg));
+    }
+
+    /// Only ever called with the `user_data` pointer passed to sokol_app,
+    /// which points to the `SAppImpl` owned by `sapp_run()`.
+    fn get<'a>(user_data: *mut c_void) -> &'a SAppImpl {
         unsafe {
-            let app_ptr = user_data as *mut SAppImpl;
-            &mut *app_ptr
+            &*(user_data as *const SAppImpl)
         }
     }
 }
 
+/// Runs the application, and returns its exit code.
+///
+/// If one of the application callbacks panicked, the panic is returned after
+/// sokol_app has shut down.
 pub fn sapp_run<S: SApp + 'static>(callbacks: S,
-                                   desc: SAppDesc) -> i32 {
+                                   desc: SAppDesc) -> Result<i32, SAppPanic> {
     let app = SAppImpl::new(callbacks, desc);
+    let mut arena = MarshalArena::new();
 
-    unsafe {
-        ffi::sapp_run(&ffi::sapp_make_desc(&app))
+    let exit_code = unsafe {
+        ffi::sapp_run(&ffi::sapp_make_desc(&app, &mut arena))
+    };
+
+    match app.take_panic().or_else(crate::audio::take_saudio_stream_panic) {
+        Some(payload) => Err(SAppPanic::new(payload)),
+        None => Ok(exit_code),
     }
 }
 
 pub fn sapp_isvalid() -> bool {
+    if headless::headless_state().is_some() {
+        return true;
+    }
+
     unsafe {
         ffi::sapp_isvalid()
     }
 }
 
 pub fn sapp_width() -> i32 {
+    if let Some(state) = headless::headless_state() {
+        return state.framebuffer_width();
+    }
+
     unsafe {
         ffi::sapp_width()
     }
 }
 
 pub fn sapp_height() -> i32 {
+    if let Some(state) = headless::headless_state() {
+        return state.framebuffer_height();
+    }
+
     unsafe {
         ffi::sapp_height()
     }
 }
 
 pub fn sapp_high_dpi() -> bool {
+    if let Some(state) = headless::headless_state() {
+        return state.high_dpi;
+    }
+
     unsafe {
         ffi::sapp_high_dpi()
     }
 }
 
 pub fn sapp_dpi_scale() -> f32 {
+    if let Some(state) = headless::headless_state() {
+        return state.dpi_scale;
+    }
+
     unsafe {
         ffi::sapp_dpi_scale()
     }
 }
 
 pub fn sapp_show_keyboard(visible: bool) {
+    if headless::update_headless_state(|state| state.keyboard_shown = visible) {
+        return;
+    }
+
     unsafe {
         ffi::sapp_show_keyboard(visible);
     }
 }
 
 pub fn sapp_keyboard_shown() -> bool {
+    if let Some(state) = headless::headless_state() {
+        return state.keyboard_shown;
+    }
+
     unsafe {
         ffi::sapp_keyboard_shown()
     }
 }
 
+/// Shows or hides the mouse cursor while it is over the window.
+///
+/// Only implemented for X11, and ignored on other platforms.
+pub fn sapp_show_mouse(shown: bool) {
+    if headless::update_headless_state(|state| state.mouse_shown = shown) {
+        return;
+    }
+
+    unsafe {
+        ffi::sokol_rs_show_mouse(shown);
+    }
+}
+
+pub fn sapp_mouse_shown() -> bool {
+    if let Some(state) = headless::headless_state() {
+        return state.mouse_shown;
+    }
+
+    unsafe {
+        ffi::sokol_rs_mouse_shown()
+    }
+}
+
+/// Selects a standard cursor shape.
+///
+/// Only implemented for X11, and ignored on other platforms.
+pub fn sapp_set_mouse_cursor(cursor: SAppMouseCursor) {
+    if headless::update_headless_state(|state| state.mouse_cursor = cursor) {
+        return;
+    }
+
+    unsafe {
+        ffi::sokol_rs_set_mouse_cursor(cursor);
+    }
+}
+
+pub fn sapp_mouse_cursor() -> SAppMouseCursor {
+    if let Some(state) = headless::headless_state() {
+        return state.mouse_cursor;
+    }
+
+    unsafe {
+        ffi::sokol_rs_get_mouse_cursor()
+    }
+}
+
+/// Locks the mouse pointer to the window and hides the cursor, e.g. for
+/// mouse look. While locked, `SAppEvent::mouse_dx` and `mouse_dy` report
+/// the relative mouse movement.
+///
+/// The pointer is released while the window is iconified or unfocused, and
+/// grabbed again once it's active.
+///
+/// Only implemented for X11. Check `sapp_mouse_locked()` to find out if
+/// locking succeeded.
+pub fn sapp_lock_mouse(locked: bool) {
+    if headless::update_headless_state(|state| state.mouse_locked = locked) {
+        return;
+    }
+
+    unsafe {
+        ffi::sokol_rs_lock_mouse(locked);
+    }
+}
+
+pub fn sapp_mouse_locked() -> bool {
+    if let Some(state) = headless::headless_state() {
+        return state.mouse_locked;
+    }
+
+    unsafe {
+        ffi::sokol_rs_mouse_locked()
+    }
+}
+
 pub fn sapp_request_quit() {
+    if headless::update_headless_state(|state| state.quit_requested = true) {
+        return;
+    }
+
     unsafe {
         ffi::sapp_request_quit();
     }
 }
 
 pub fn sapp_cancel_quit() {
+    if headless::update_headless_state(|state| state.quit_requested = false) {
+        return;
+    }
+
     unsafe {
         ffi::sapp_cancel_quit();
     }
 }
 
 pub fn sapp_quit() {
+    if headless::update_headless_state(|state| state.quit_ordered = true) {
+        return;
+    }
+
     unsafe {
         ffi::sapp_quit();
     }
 }
 
+/// Returns the current frame count, or the recorded frame count while an
+/// `EventReplayer` is active.
 pub fn sapp_frame_count() -> u64 {
+    if let Some(frame_count) = record::replay_frame_count() {
+        return frame_count;
+    }
+
+    if let Some(state) = headless::headless_state() {
+        return state.frame_count;
+    }
+
     unsafe {
         ffi::sapp_frame_count()
     }
@@ -613,3 +899,91 @@ pub fn sapp_gles2() -> bool {
         ffi::sapp_gles2()
     }
 }
+
+pub fn sapp_metal_get_device() -> *const c_void {
+    unsafe {
+        ffi::sapp_metal_get_device()
+    }
+}
+
+pub fn sapp_metal_get_renderpass_descriptor() -> *const c_void {
+    unsafe {
+        ffi::sapp_metal_get_renderpass_descriptor()
+    }
+}
+
+pub fn sapp_metal_get_drawable() -> *const c_void {
+    unsafe {
+        ffi::sapp_metal_get_drawable()
+    }
+}
+
+pub fn sapp_macos_get_window() -> *const c_void {
+    unsafe {
+        ffi::sapp_macos_get_window()
+    }
+}
+
+pub fn sapp_ios_get_window() -> *const c_void {
+    unsafe {
+        ffi::sapp_ios_get_window()
+    }
+}
+
+pub fn sapp_d3d11_get_device() -> *const c_void {
+    unsafe {
+        ffi::sapp_d3d11_get_device()
+    }
+}
+
+pub fn sapp_d3d11_get_device_context() -> *const c_void {
+    unsafe {
+        ffi::sapp_d3d11_get_device_context()
+    }
+}
+
+pub fn sapp_d3d11_get_render_target_view() -> *const c_void {
+    unsafe {
+        ffi::sapp_d3d11_get_render_target_view()
+    }
+}
+
+pub fn sapp_d3d11_get_depth_stencil_view() -> *const c_void {
+    unsafe {
+        ffi::sapp_d3d11_get_depth_stencil_view()
+    }
+}
+
+pub fn sapp_win32_get_hwnd() -> *const c_void {
+    unsafe {
+        ffi::sapp_win32_get_hwnd()
+    }
+}
+
+/// Returns the X11 `Display*`, or null on other platforms.
+pub fn sapp_x11_get_display() -> *mut c_void {
+    unsafe {
+        ffi::sokol_rs_x11_get_display()
+    }
+}
+
+/// Returns the X11 window, or 0 on other platforms.
+pub fn sapp_x11_get_window() -> c_ulong {
+    unsafe {
+        ffi::sokol_rs_x11_get_window()
+    }
+}
+
+/// Returns the X11 screen number, or 0 on other platforms.
+pub fn sapp_x11_get_screen() -> c_int {
+    unsafe {
+        ffi::sokol_rs_x11_get_screen()
+    }
+}
+
+/// Returns the `GLXContext`, or null on other platforms.
+pub fn sapp_glx_get_context() -> *const c_void {
+    unsafe {
+        ffi::sokol_rs_glx_get_context()
+    }
+}
@@ -0,0 +1,755 @@
+//! Named actions and axes, bound to keys and mouse buttons.
+//!
+//! An `ActionMap` maps action and axis names to one or more chords. It is
+//! queried with an `InputState`, and can be converted to and from a text
+//! format, so that bindings can be stored in a configuration file:
+//!
+//! ```text
+//! # actions are bound to one or more chords
+//! action save = Ctrl+S, Ctrl+Shift+S
+//! action fire = MouseLeft, Space
+//!
+//! # axes are bound to negative/positive chords
+//! axis zoom = Minus/Equal
+//!
+//! # 2D axes are bound to left/right/down/up chords
+//! axis2d move = A/D/S/W, Left/Right/Down/Up
+//! ```
+//!
+//! Input names are the `SAppKeycode` names without the `Key` prefix, plus
+//! `MouseLeft`, `MouseRight` and `MouseMiddle`. Modifiers are `Shift`,
+//! `Ctrl`, `Alt` and `Super`. Names are not case sensitive.
+//!
+//! Action and axis names can't be empty, and can't contain whitespace or
+//! `=`.
+
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::*;
+
+/// A key or mouse button.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InputButton {
+    Key(SAppKeycode),
+    Mouse(SAppMouseButton),
+}
+
+/// A key or mouse button, together with the modifier keys which must be held.
+///
+/// The held modifier keys must match exactly, e.g. `S` doesn't match if
+/// `Ctrl` is held, so that it doesn't fire together with `Ctrl+S`. A modifier
+/// key used as the button itself, like `LeftShift`, is not counted as a
+/// modifier.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Chord {
+    pub modifiers: SAppModifier,
+    pub button: InputButton,
+}
+
+impl Chord {
+    pub fn key(key: SAppKeycode) -> Chord {
+        Chord {
+            modifiers: SAppModifier::empty(),
+            button: InputButton::Key(key),
+        }
+    }
+
+    pub fn mouse(button: SAppMouseButton) -> Chord {
+        Chord {
+            modifiers: SAppModifier::empty(),
+            button: InputButton::Mouse(button),
+        }
+    }
+
+    /// Returns the chord, with additional modifier keys required.
+    pub fn with(self, modifiers: SAppModifier) -> Chord {
+        Chord {
+            modifiers: self.modifiers | modifiers,
+            button: self.button,
+        }
+    }
+
+    /// Returns true if the chord is held down.
+    pub fn down(&self, input: &InputState) -> bool {
+        self.modifiers_held(input) && match self.button {
+            InputButton::Key(key) => input.key_down(key),
+            InputButton::Mouse(button) => input.mouse_button_down(button),
+        }
+    }
+
+    /// Returns true if the chord has been pressed during the current frame.
+    pub fn pressed(&self, input: &InputState) -> bool {
+        self.modifiers_held(input) && match self.button {
+            InputButton::Key(key) => input.key_pressed(key),
+            InputButton::Mouse(button) => input.mouse_button_pressed(button),
+        }
+    }
+
+    /// Returns true if the chord has been released during the current frame.
+    pub fn released(&self, input: &InputState) -> bool {
+        self.modifiers_held(input) && match self.button {
+            InputButton::Key(key) => input.key_released(key),
+            InputButton::Mouse(button) => input.mouse_button_released(button),
+        }
+    }
+
+    fn modifiers_held(&self, input: &InputState) -> bool {
+        let mut held = input.modifiers();
+        if let InputButton::Key(key) = self.button {
+            held.remove(key_modifier(key));
+        }
+        held == self.modifiers
+    }
+}
+
+fn key_modifier(key: SAppKeycode) -> SAppModifier {
+    match key {
+        SAppKeycode::KeyLeftShift | SAppKeycode::KeyRightShift => SAppModifier::SHIFT,
+        SAppKeycode::KeyLeftControl | SAppKeycode::KeyRightControl => SAppModifier::CONTROL,
+        SAppKeycode::KeyLeftAlt | SAppKeycode::KeyRightAlt => SAppModifier::ALT,
+        SAppKeycode::KeyLeftSuper | SAppKeycode::KeyRightSuper => SAppModifier::SUPER,
+        _ => SAppModifier::empty(),
+    }
+}
+
+const MODIFIER_NAMES: [(SAppModifier, &str); 4] = [
+    (SAppModifier::SHIFT, "Shift"),
+    (SAppModifier::CONTROL, "Ctrl"),
+    (SAppModifier::ALT, "Alt"),
+    (SAppModifier::SUPER, "Super"),
+];
+
+const MOUSE_BUTTON_NAMES: [(SAppMouseButton, &str); 3] = [
+    (SAppMouseButton::Left, "MouseLeft"),
+    (SAppMouseButton::Right, "MouseRight"),
+    (SAppMouseButton::Middle, "MouseMiddle"),
+];
+
+const KEY_NAMES: [(SAppKeycode, &str); 120] = [
+    (SAppKeycode::KeySpace, "Space"),
+    (SAppKeycode::KeyApostrophe, "Apostrophe"),
+    (SAppKeycode::KeyComma, "Comma"),
+    (SAppKeycode::KeyMinus, "Minus"),
+    (SAppKeycode::KeyPeriod, "Period"),
+    (SAppKeycode::KeySlash, "Slash"),
+    (SAppKeycode::Key0, "0"),
+    (SAppKeycode::Key1, "1"),
+    (SAppKeycode::Key2, "2"),
+    (SAppKeycode::Key3, "3"),
+    (SAppKeycode::Key4, "4"),
+    (SAppKeycode::Key5, "5"),
+    (SAppKeycode::Key6, "6"),
+    (SAppKeycode::Key7, "7"),
+    (SAppKeycode::Key8, "8"),
+    (SAppKeycode::Key9, "9"),
+    (SAppKeycode::KeySemicolon, "Semicolon"),
+    (SAppKeycode::KeyEqual, "Equal"),
+    (SAppKeycode::KeyA, "A"),
+    (SAppKeycode::KeyB, "B"),
+    (SAppKeycode::KeyC, "C"),
+    (SAppKeycode::KeyD, "D"),
+    (SAppKeycode::KeyE, "E"),
+    (SAppKeycode::KeyF, "F"),
+    (SAppKeycode::KeyG, "G"),
+    (SAppKeycode::KeyH, "H"),
+    (SAppKeycode::KeyI, "I"),
+    (SAppKeycode::KeyJ, "J"),
+    (SAppKeycode::KeyK, "K"),
+    (SAppKeycode::KeyL, "L"),
+    (SAppKeycode::KeyM, "M"),
+    (SAppKeycode::KeyN, "N"),
+    (SAppKeycode::KeyO, "O"),
+    (SAppKeycode::KeyP, "P"),
+    (SAppKeycode::KeyQ, "Q"),
+    (SAppKeycode::KeyR, "R"),
+    (SAppKeycode::KeyS, "S"),
+    (SAppKeycode::KeyT, "T"),
+    (SAppKeycode::KeyU, "U"),
+    (SAppKeycode::KeyV, "V"),
+    (SAppKeycode::KeyW, "W"),
+    (SAppKeycode::KeyX, "X"),
+    (SAppKeycode::KeyY, "Y"),
+    (SAppKeycode::KeyZ, "Z"),
+    (SAppKeycode::KeyLeftBracket, "LeftBracket"),
+    (SAppKeycode::KeyBackslash, "Backslash"),
+    (SAppKeycode::KeyRightBracket, "RightBracket"),
+    (SAppKeycode::KeyGraveAccent, "GraveAccent"),
+    (SAppKeycode::KeyWorld1, "World1"),
+    (SAppKeycode::KeyWorld2, "World2"),
+    (SAppKeycode::KeyEscape, "Escape"),
+    (SAppKeycode::KeyEnter, "Enter"),
+    (SAppKeycode::KeyTab, "Tab"),
+    (SAppKeycode::KeyBackspace, "Backspace"),
+    (SAppKeycode::KeyInsert, "Insert"),
+    (SAppKeycode::KeyDelete, "Delete"),
+    (SAppKeycode::KeyRight, "Right"),
+    (SAppKeycode::KeyLeft, "Left"),
+    (SAppKeycode::KeyDown, "Down"),
+    (SAppKeycode::KeyUp, "Up"),
+    (SAppKeycode::KeyPageUp, "PageUp"),
+    (SAppKeycode::KeyPageDown, "PageDown"),
+    (SAppKeycode::KeyHome, "Home"),
+    (SAppKeycode::KeyEnd, "End"),
+    (SAppKeycode::KeyCapsLock, "CapsLock"),
+    (SAppKeycode::KeyScrollLock, "ScrollLock"),
+    (SAppKeycode::KeyNumLock, "NumLock"),
+    (SAppKeycode::KeyPrintScreen, "PrintScreen"),
+    (SAppKeycode::KeyPause, "Pause"),
+    (SAppKeycode::KeyF1, "F1"),
+    (SAppKeycode::KeyF2, "F2"),
+    (SAppKeycode::KeyF3, "F3"),
+    (SAppKeycode::KeyF4, "F4"),
+    (SAppKeycode::KeyF5, "F5"),
+    (SAppKeycode::KeyF6, "F6"),
+    (SAppKeycode::KeyF7, "F7"),
+    (SAppKeycode::KeyF8, "F8"),
+    (SAppKeycode::KeyF9, "F9"),
+    (SAppKeycode::KeyF10, "F10"),
+    (SAppKeycode::KeyF11, "F11"),
+    (SAppKeycode::KeyF12, "F12"),
+    (SAppKeycode::KeyF13, "F13"),
+    (SAppKeycode::KeyF14, "F14"),
+    (SAppKeycode::KeyF15, "F15"),
+    (SAppKeycode::KeyF16, "F16"),
+    (SAppKeycode::KeyF17, "F17"),
+    (SAppKeycode::KeyF18, "F18"),
+    (SAppKeycode::KeyF19, "F19"),
+    (SAppKeycode::KeyF20, "F20"),
+    (SAppKeycode::KeyF21, "F21"),
+    (SAppKeycode::KeyF22, "F22"),
+    (SAppKeycode::KeyF23, "F23"),
+    (SAppKeycode::KeyF24, "F24"),
+    (SAppKeycode::KeyF25, "F25"),
+    (SAppKeycode::KeyKP0, "KP0"),
+    (SAppKeycode::KeyKP1, "KP1"),
+    (SAppKeycode::KeyKP2, "KP2"),
+    (SAppKeycode::KeyKP3, "KP3"),
+    (SAppKeycode::KeyKP4, "KP4"),
+    (SAppKeycode::KeyKP5, "KP5"),
+    (SAppKeycode::KeyKP6, "KP6"),
+    (SAppKeycode::KeyKP7, "KP7"),
+    (SAppKeycode::KeyKP8, "KP8"),
+    (SAppKeycode::KeyKP9, "KP9"),
+    (SAppKeycode::KeyKPDecimal, "KPDecimal"),
+    (SAppKeycode::KeyKPDivide, "KPDivide"),
+    (SAppKeycode::KeyKPMultiply, "KPMultiply"),
+    (SAppKeycode::KeyKPSubtract, "KPSubtract"),
+    (SAppKeycode::KeyKPAdd, "KPAdd"),
+    (SAppKeycode::KeyKPEnter, "KPEnter"),
+    (SAppKeycode::KeyKPEqual, "KPEqual"),
+    (SAppKeycode::KeyLeftShift, "LeftShift"),
+    (SAppKeycode::KeyLeftControl, "LeftControl"),
+    (SAppKeycode::KeyLeftAlt, "LeftAlt"),
+    (SAppKeycode::KeyLeftSuper, "LeftSuper"),
+    (SAppKeycode::KeyRightShift, "RightShift"),
+    (SAppKeycode::KeyRightControl, "RightControl"),
+    (SAppKeycode::KeyRightAlt, "RightAlt"),
+    (SAppKeycode::KeyRightSuper, "RightSuper"),
+    (SAppKeycode::KeyMenu, "Menu"),
+];
+
+impl SAppKeycode {
+    /// The name of the key, as used by `ActionMap`.
+    pub fn name(self) -> &'static str {
+        KEY_NAMES.iter()
+            .find(|(key, _)| *key == self)
+            .map_or("Invalid", |(_, name)| name)
+    }
+
+    /// Looks up a key by its numeric key code.
+    pub fn from_code(code: i32) -> Option<SAppKeycode> {
+        KEY_NAMES.iter()
+            .find(|(key, _)| *key as i32 == code)
+            .map(|(key, _)| *key)
+    }
+
+    /// Looks up a key by name. The name is not case sensitive.
+    pub fn from_name(name: &str) -> Option<SAppKeycode> {
+        KEY_NAMES.iter()
+            .find(|(_, key_name)| key_name.eq_ignore_ascii_case(name))
+            .map(|(key, _)| *key)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in MODIFIER_NAMES.iter() {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.button {
+            InputButton::Key(key) => write!(f, "{}", key.name()),
+            InputButton::Mouse(button) => {
+                let name = MOUSE_BUTTON_NAMES.iter()
+                    .find(|(b, _)| *b == button)
+                    .map_or("MouseInvalid", |(_, name)| name);
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+impl FromStr for Chord {
+    type Err = ParseErrorKind;
+
+    fn from_str(s: &str) -> Result<Chord, ParseErrorKind> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let button_name = parts.pop().unwrap_or("");
+
+        let mut modifiers = SAppModifier::empty();
+        for part in parts {
+            match MODIFIER_NAMES.iter().find(|(_, name)| name.eq_ignore_ascii_case(part)) {
+                Some((modifier, _)) => modifiers |= *modifier,
+                None => return Err(ParseErrorKind::UnknownModifier(part.to_string())),
+            }
+        }
+
+        let button = if let Some(key) = SAppKeycode::from_name(button_name) {
+            InputButton::Key(key)
+        } else if let Some((button, _)) = MOUSE_BUTTON_NAMES.iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(button_name)) {
+            InputButton::Mouse(*button)
+        } else {
+            return Err(ParseErrorKind::UnknownInput(button_name.to_string()));
+        };
+
+        Ok(Chord {
+            modifiers,
+            button,
+        })
+    }
+}
+
+/// A pair of chords, driving an axis towards -1 and +1.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AxisBinding {
+    pub negative: Chord,
+    pub positive: Chord,
+}
+
+impl AxisBinding {
+    fn value(&self, input: &InputState) -> f32 {
+        let mut value = 0.0;
+        if self.negative.down(input) {
+            value -= 1.0;
+        }
+        if self.positive.down(input) {
+            value += 1.0;
+        }
+        value
+    }
+}
+
+/// Two pairs of chords, driving a 2D axis. `y` is positive for "up".
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Axis2dBinding {
+    pub x: AxisBinding,
+    pub y: AxisBinding,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Bindings {
+    Action(Vec<Chord>),
+    Axis(Vec<AxisBinding>),
+    Axis2d(Vec<Axis2dBinding>),
+}
+
+/// Maps action and axis names to their bindings.
+///
+/// Bindings keep the order in which they were added, which is also the
+/// order of the text representation returned by `to_string()`.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct ActionMap {
+    bindings: Vec<(String, Bindings)>,
+}
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        Default::default()
+    }
+
+    /// Binds a chord to an action. An action may have multiple bindings.
+    ///
+    /// A name previously used by an axis is rebound as an action. Names which
+    /// can't be written to the text format are rejected.
+    pub fn bind_action(&mut self, name: &str, chord: Chord) -> Result<(), InvalidNameError> {
+        match self.entry(name)? {
+            Bindings::Action(chords) => chords.push(chord),
+            bindings => *bindings = Bindings::Action(vec![chord]),
+        }
+        Ok(())
+    }
+
+    pub fn bind_axis(&mut self, name: &str, negative: Chord, positive: Chord) -> Result<(), InvalidNameError> {
+        let binding = AxisBinding { negative, positive };
+        match self.entry(name)? {
+            Bindings::Axis(axes) => axes.push(binding),
+            bindings => *bindings = Bindings::Axis(vec![binding]),
+        }
+        Ok(())
+    }
+
+    pub fn bind_axis2d(&mut self, name: &str, left: Chord, right: Chord, down: Chord, up: Chord)
+                       -> Result<(), InvalidNameError> {
+        let binding = Axis2dBinding {
+            x: AxisBinding { negative: left, positive: right },
+            y: AxisBinding { negative: down, positive: up },
+        };
+        match self.entry(name)? {
+            Bindings::Axis2d(axes) => axes.push(binding),
+            bindings => *bindings = Bindings::Axis2d(vec![binding]),
+        }
+        Ok(())
+    }
+
+    /// Removes all bindings of an action or axis.
+    pub fn unbind(&mut self, name: &str) {
+        self.bindings.retain(|(n, _)| n != name);
+    }
+
+    /// The chords bound to an action.
+    pub fn action_chords(&self, name: &str) -> &[Chord] {
+        match self.get(name) {
+            Some(Bindings::Action(chords)) => chords,
+            _ => &[],
+        }
+    }
+
+    pub fn axis_bindings(&self, name: &str) -> &[AxisBinding] {
+        match self.get(name) {
+            Some(Bindings::Axis(axes)) => axes,
+            _ => &[],
+        }
+    }
+
+    pub fn axis2d_bindings(&self, name: &str) -> &[Axis2dBinding] {
+        match self.get(name) {
+            Some(Bindings::Axis2d(axes)) => axes,
+            _ => &[],
+        }
+    }
+
+    /// Returns true if any chord bound to the action is held down.
+    pub fn action_down(&self, input: &InputState, name: &str) -> bool {
+        self.action_chords(name).iter().any(|chord| chord.down(input))
+    }
+
+    /// Returns true if any chord bound to the action has been pressed
+    /// during the current frame.
+    pub fn action_pressed(&self, input: &InputState, name: &str) -> bool {
+        self.action_chords(name).iter().any(|chord| chord.pressed(input))
+    }
+
+    /// Returns true if any chord bound to the action has been released
+    /// during the current frame.
+    pub fn action_released(&self, input: &InputState, name: &str) -> bool {
+        self.action_chords(name).iter().any(|chord| chord.released(input))
+    }
+
+    /// The value of an axis, in the range [-1, 1].
+    pub fn axis(&self, input: &InputState, name: &str) -> f32 {
+        let value: f32 = self.axis_bindings(name).iter()
+            .map(|axis| axis.value(input))
+            .sum();
+        value.clamp(-1.0, 1.0)
+    }
+
+    /// The value of a 2D axis, with each component in the range [-1, 1].
+    pub fn axis2d(&self, input: &InputState, name: &str) -> (f32, f32) {
+        let (x, y) = self.axis2d_bindings(name).iter()
+            .fold((0.0f32, 0.0f32), |(x, y), axis| {
+                (x + axis.x.value(input), y + axis.y.value(input))
+            });
+        (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0))
+    }
+
+    fn get(&self, name: &str) -> Option<&Bindings> {
+        self.bindings.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, bindings)| bindings)
+    }
+
+    fn entry(&mut self, name: &str) -> Result<&mut Bindings, InvalidNameError> {
+        let index = match self.bindings.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '=') {
+                    return Err(InvalidNameError {
+                        name: name.to_string(),
+                    });
+                }
+                self.bindings.push((name.to_string(), Bindings::Action(Vec::new())));
+                self.bindings.len() - 1
+            }
+        };
+        Ok(&mut self.bindings[index].1)
+    }
+}
+
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, bindings) in self.bindings.iter() {
+            match bindings {
+                Bindings::Action(chords) => {
+                    write!(f, "action {} =", name)?;
+                    for (i, chord) in chords.iter().enumerate() {
+                        write!(f, "{} {}", if i > 0 { "," } else { "" }, chord)?;
+                    }
+                }
+                Bindings::Axis(axes) => {
+                    write!(f, "axis {} =", name)?;
+                    for (i, axis) in axes.iter().enumerate() {
+                        write!(f, "{} {}/{}", if i > 0 { "," } else { "" },
+                               axis.negative, axis.positive)?;
+                    }
+                }
+                Bindings::Axis2d(axes) => {
+                    write!(f, "axis2d {} =", name)?;
+                    for (i, axis) in axes.iter().enumerate() {
+                        write!(f, "{} {}/{}/{}/{}", if i > 0 { "," } else { "" },
+                               axis.x.negative, axis.x.positive, axis.y.negative, axis.y.positive)?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ActionMap {
+    type Err = ActionMapParseError;
+
+    /// Parses the text format described in the module documentation.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    fn from_str(s: &str) -> Result<ActionMap, ActionMapParseError> {
+        let mut map = ActionMap::new();
+
+        for (index, line) in s.lines().enumerate() {
+            parse_line(&mut map, line.trim()).map_err(|kind| ActionMapParseError {
+                line: index + 1,
+                kind,
+            })?;
+        }
+
+        Ok(map)
+    }
+}
+
+fn parse_line(map: &mut ActionMap, line: &str) -> Result<(), ParseErrorKind> {
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(());
+    }
+
+    let mut split = line.splitn(2, '=');
+    let head = split.next().unwrap_or("");
+    let bindings = split.next().ok_or(ParseErrorKind::MissingBindings)?;
+
+    let mut words = head.split_whitespace();
+    let kind = words.next().unwrap_or("");
+    let name = words.next().ok_or(ParseErrorKind::MissingName)?;
+    if let Some(word) = words.next() {
+        return Err(ParseErrorKind::UnexpectedToken(word.to_string()));
+    }
+
+    let expected = match kind {
+        "action" => 1,
+        "axis" => 2,
+        "axis2d" => 4,
+        _ => return Err(ParseErrorKind::UnknownKind(kind.to_string())),
+    };
+
+    if bindings.trim().is_empty() {
+        return Err(ParseErrorKind::MissingBindings);
+    }
+
+    for binding in bindings.split(',') {
+        let chords = binding.split('/')
+            .map(|chord| chord.trim().parse::<Chord>())
+            .collect::<Result<Vec<Chord>, ParseErrorKind>>()?;
+
+        match chords.as_slice() {
+            [chord] if expected == 1 => map.bind_action(name, *chord)?,
+            [negative, positive] if expected == 2 => map.bind_axis(name, *negative, *positive)?,
+            [left, right, down, up] if expected == 4 => map.bind_axis2d(name, *left, *right, *down, *up)?,
+            _ => return Err(ParseErrorKind::ChordCount {
+                expected,
+                found: chords.len(),
+            }),
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ParseErrorKind {
+    /// The line doesn't start with `action`, `axis` or `axis2d`.
+    UnknownKind(String),
+    MissingName,
+    InvalidName(String),
+    /// There is more than one word before the `=`.
+    UnexpectedToken(String),
+    MissingBindings,
+    UnknownModifier(String),
+    UnknownInput(String),
+    /// A binding has the wrong number of `/`-separated chords for its kind.
+    ChordCount {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnknownKind(kind) => {
+                write!(f, "unknown binding kind '{}', expected action, axis or axis2d", kind)
+            }
+            ParseErrorKind::MissingName => {
+                write!(f, "missing name")
+            }
+            ParseErrorKind::InvalidName(name) => {
+                write!(f, "invalid name '{}'", name)
+            }
+            ParseErrorKind::UnexpectedToken(token) => {
+                write!(f, "unexpected '{}' before '='", token)
+            }
+            ParseErrorKind::MissingBindings => {
+                write!(f, "missing bindings, expected '= <chord>'")
+            }
+            ParseErrorKind::UnknownModifier(name) => {
+                write!(f, "unknown modifier '{}'", name)
+            }
+            ParseErrorKind::UnknownInput(name) => {
+                write!(f, "unknown key or mouse button '{}'", name)
+            }
+            ParseErrorKind::ChordCount { expected, found } => {
+                write!(f, "expected {} chord(s) per binding, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl error::Error for ParseErrorKind {}
+
+impl From<InvalidNameError> for ParseErrorKind {
+    fn from(err: InvalidNameError) -> ParseErrorKind {
+        ParseErrorKind::InvalidName(err.name)
+    }
+}
+
+/// An error reported by the `ActionMap::bind_*()` functions, if a name is
+/// empty, or contains whitespace or `=`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct InvalidNameError {
+    pub name: String,
+}
+
+impl fmt::Display for InvalidNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid action name '{}', expected no whitespace or '='", self.name)
+    }
+}
+
+impl error::Error for InvalidNameError {}
+
+/// An error reported by `ActionMap::from_str()`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ActionMapParseError {
+    /// The line number, starting at 1.
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ActionMapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl error::Error for ActionMapParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(input: &mut InputState, key: SAppKeycode, modifiers: SAppModifier) {
+        let mut event = SAppEvent::new(SAppEventType::KeyDown);
+        event.key_code = key;
+        event.modifiers = modifiers;
+        input.handle_event(&event);
+    }
+
+    #[test]
+    fn chord_modifiers_match_exactly() {
+        let s = Chord::key(SAppKeycode::KeyS);
+        let ctrl_s = Chord::key(SAppKeycode::KeyS).with(SAppModifier::CONTROL);
+
+        let mut input = InputState::new();
+        key_down(&mut input, SAppKeycode::KeyS, SAppModifier::CONTROL);
+        assert!(ctrl_s.down(&input));
+        assert!(!s.down(&input));
+
+        let mut input = InputState::new();
+        key_down(&mut input, SAppKeycode::KeyS, SAppModifier::empty());
+        assert!(s.down(&input));
+        assert!(!ctrl_s.down(&input));
+    }
+
+    #[test]
+    fn chord_ignores_its_own_modifier() {
+        let shift = Chord::key(SAppKeycode::KeyLeftShift);
+
+        let mut input = InputState::new();
+        key_down(&mut input, SAppKeycode::KeyLeftShift, SAppModifier::SHIFT);
+        assert!(shift.down(&input));
+    }
+
+    #[test]
+    fn invalid_names_are_rejected() {
+        let mut map = ActionMap::new();
+        let chord = Chord::key(SAppKeycode::KeyA);
+        for name in ["", "two words", "a=b", "tab\tname"].iter() {
+            assert_eq!(map.bind_action(name, chord), Err(InvalidNameError { name: name.to_string() }));
+        }
+        assert_eq!(map, ActionMap::new());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut map = ActionMap::new();
+        map.bind_action("save", Chord::key(SAppKeycode::KeyS).with(SAppModifier::CONTROL)).unwrap();
+        map.bind_action("save", Chord::key(SAppKeycode::KeyS)
+            .with(SAppModifier::CONTROL | SAppModifier::SHIFT)).unwrap();
+        map.bind_action("menu/fire,alt", Chord::mouse(SAppMouseButton::Left)).unwrap();
+        map.bind_axis("zoom", Chord::key(SAppKeycode::KeyMinus), Chord::key(SAppKeycode::KeyEqual)).unwrap();
+        map.bind_axis2d("move",
+                        Chord::key(SAppKeycode::KeyA), Chord::key(SAppKeycode::KeyD),
+                        Chord::key(SAppKeycode::KeyS), Chord::key(SAppKeycode::KeyW)).unwrap();
+
+        let text = map.to_string();
+        assert_eq!(text, "action save = Ctrl+S, Shift+Ctrl+S\n\
+                          action menu/fire,alt = MouseLeft\n\
+                          axis zoom = Minus/Equal\n\
+                          axis2d move = A/D/S/W\n");
+        assert_eq!(text.parse::<ActionMap>(), Ok(map));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = "action save = Ctrl+S\naction fire = Hyper+A".parse::<ActionMap>().unwrap_err();
+        assert_eq!(err, ActionMapParseError {
+            line: 2,
+            kind: ParseErrorKind::UnknownModifier("Hyper".to_string()),
+        });
+
+        let err = "axis zoom = Minus".parse::<ActionMap>().unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::ChordCount { expected: 2, found: 1 });
+    }
+}
@@ -0,0 +1,227 @@
+//! Closure-based application setup.
+
+use super::*;
+
+type InitFn<S> = Box<dyn FnOnce() -> S>;
+type FrameFn<S> = Box<dyn FnMut(&mut S)>;
+type EventFn<S> = Box<dyn FnMut(&mut S, SAppEvent)>;
+type CleanupFn<S> = Box<dyn FnOnce(S)>;
+type FailFn = Box<dyn FnMut(&str)>;
+
+/// Runs an application from a set of closures, as an alternative to
+/// implementing the `SApp` trait.
+///
+/// The init closure is called once sokol_app has created the window and
+/// 3D context. It returns the application state, which is then passed to
+/// all other closures.
+///
+/// ```ignore
+/// AppBuilder::new(|| {
+///         sg_setup(&Default::default());
+///         SgPassAction::default()
+///     })
+///     .frame(|pass_action| {
+///         sg_begin_default_pass(pass_action, sapp_width(), sapp_height());
+///         sg_end_pass();
+///         sg_commit();
+///     })
+///     .cleanup(|_| sg_shutdown())
+///     .desc(SAppDesc {
+///         window_title: "tool".to_string(),
+///         ..Default::default()
+///     })
+///     .run()
+///     .unwrap_or_else(|panic| panic.resume());
+/// ```
+pub struct AppBuilder<S> {
+    desc: SAppDesc,
+    init: InitFn<S>,
+    frame: Option<FrameFn<S>>,
+    event: Option<EventFn<S>>,
+    cleanup: Option<CleanupFn<S>>,
+    fail: Option<FailFn>,
+}
+
+impl<S: 'static> AppBuilder<S> {
+    pub fn new<F>(init: F) -> AppBuilder<S>
+        where F: FnOnce() -> S + 'static {
+        AppBuilder {
+            desc: Default::default(),
+            init: Box::new(init),
+            frame: None,
+            event: None,
+            cleanup: None,
+            fail: None,
+        }
+    }
+
+    pub fn desc(mut self, desc: SAppDesc) -> AppBuilder<S> {
+        self.desc = desc;
+        self
+    }
+
+    pub fn frame<F>(mut self, frame: F) -> AppBuilder<S>
+        where F: FnMut(&mut S) + 'static {
+        self.frame = Some(Box::new(frame));
+        self
+    }
+
+    pub fn event<F>(mut self, event: F) -> AppBuilder<S>
+        where F: FnMut(&mut S, SAppEvent) + 'static {
+        self.event = Some(Box::new(event));
+        self
+    }
+
+    /// The cleanup closure takes ownership of the application state.
+    pub fn cleanup<F>(mut self, cleanup: F) -> AppBuilder<S>
+        where F: FnOnce(S) + 'static {
+        self.cleanup = Some(Box::new(cleanup));
+        self
+    }
+
+    pub fn fail<F>(mut self, fail: F) -> AppBuilder<S>
+        where F: FnMut(&str) + 'static {
+        self.fail = Some(Box::new(fail));
+        self
+    }
+
+    /// Runs the application, see `sapp_run()`.
+    pub fn run(self) -> Result<i32, SAppPanic> {
+        let (app, desc) = self.build();
+        sapp_run(app, desc)
+    }
+
+    fn build(self) -> (ClosureApp<S>, SAppDesc) {
+        let app = ClosureApp {
+            init: Some(self.init),
+            state: None,
+            frame: self.frame,
+            event: self.event,
+            cleanup: self.cleanup,
+            fail: self.fail,
+        };
+
+        (app, self.desc)
+    }
+}
+
+struct ClosureApp<S> {
+    init: Option<InitFn<S>>,
+    state: Option<S>,
+    frame: Option<FrameFn<S>>,
+    event: Option<EventFn<S>>,
+    cleanup: Option<CleanupFn<S>>,
+    fail: Option<FailFn>,
+}
+
+impl<S> SApp for ClosureApp<S> {
+    fn sapp_init(&mut self) {
+        if let Some(init) = self.init.take() {
+            self.state = Some(init());
+        }
+    }
+
+    fn sapp_frame(&mut self) {
+        if let (Some(state), Some(frame)) = (self.state.as_mut(), self.frame.as_mut()) {
+            frame(state);
+        }
+    }
+
+    fn sapp_cleanup(&mut self) {
+        if let Some(state) = self.state.take() {
+            if let Some(cleanup) = self.cleanup.take() {
+                cleanup(state);
+            }
+        }
+    }
+
+    fn sapp_event(&mut self, event: SAppEvent) {
+        if let (Some(state), Some(handler)) = (self.state.as_mut(), self.event.as_mut()) {
+            handler(state, event);
+        }
+    }
+
+    fn sapp_fail(&mut self, msg: &str) {
+        match self.fail.as_mut() {
+            Some(fail) => fail(msg),
+            None => print!("{}", msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    #[test]
+    fn closures_share_state() {
+        let log = Log::default();
+        let (frame_log, event_log, cleanup_log, fail_log) = (log.clone(), log.clone(), log.clone(), log.clone());
+
+        let (app, desc) = AppBuilder::new(|| 10)
+            .frame(move |count| {
+                *count += 1;
+                frame_log.borrow_mut().push(format!("frame {}", count));
+            })
+            .event(move |count, event| {
+                event_log.borrow_mut().push(format!("event {} {:?}", count, event.event_type));
+            })
+            .cleanup(move |count| cleanup_log.borrow_mut().push(format!("cleanup {}", count)))
+            .fail(move |msg| fail_log.borrow_mut().push(format!("fail {}", msg)))
+            .desc(SAppDesc {
+                width: 320,
+                ..Default::default()
+            })
+            .build();
+        assert_eq!(desc.width, 320);
+
+        let mut headless = Headless::new(app, &desc);
+
+        // nothing runs before init creates the state
+        headless.frame();
+        headless.event(SAppEvent::new(SAppEventType::MouseEnter));
+        assert!(log.borrow().is_empty());
+
+        headless.init();
+        headless.frame();
+        headless.event(SAppEvent::new(SAppEventType::MouseLeave));
+        headless.frame();
+        headless.app_mut().sapp_fail("no window");
+        let mut app = headless.cleanup();
+
+        // cleanup only runs once
+        app.sapp_cleanup();
+
+        assert_eq!(*log.borrow(), [
+            "frame 11",
+            "event 11 MouseLeave",
+            "frame 12",
+            "fail no window",
+            "cleanup 12",
+        ]);
+    }
+
+    #[test]
+    fn optional_closures() {
+        let log = Log::default();
+        let frame_log = log.clone();
+
+        let (app, desc) = AppBuilder::new(|| "state")
+            .frame(move |state| frame_log.borrow_mut().push(state.to_string()))
+            .build();
+
+        let mut headless = Headless::new(app, &desc);
+        headless.init();
+        headless.event(SAppEvent::new(SAppEventType::KeyDown));
+        headless.frame();
+        headless.app_mut().sapp_fail("");
+        headless.cleanup();
+
+        assert_eq!(*log.borrow(), ["state"]);
+    }
+}
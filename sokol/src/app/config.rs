@@ -0,0 +1,421 @@
+//! Application configuration from config files and command-line arguments.
+//!
+//! `SAppDesc` fields can be read from a config file, using a subset of TOML
+//! with one `key = value` pair per line:
+//!
+//! ```text
+//! # window setup
+//! width = 1280
+//! height = 720
+//! fullscreen = false
+//! window_title = "QA build"
+//! ```
+//!
+//! The same keys can be passed as command-line arguments, e.g. `--width=1280`
+//! or `--high-dpi`. Dashes and underscores in keys are interchangeable.
+//! Boolean flags without a value are set to true. Arguments which are not
+//! applied are returned to the caller, so that applications can handle
+//! their own arguments, or report them.
+//!
+//! Config files and arguments can be combined, with arguments overriding
+//! the config file:
+//!
+//! ```ignore
+//! let mut desc = SAppDesc::from_toml(&fs::read_to_string("app.toml")?)?;
+//! for arg in desc.apply_args(env::args_os().skip(1))? {
+//!     eprintln!("unknown argument {:?}", arg);
+//! }
+//! ```
+
+use std::convert::TryFrom;
+use std::error;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::fmt;
+
+use super::*;
+
+const SAMPLE_COUNTS: [i32; 6] = [0, 1, 2, 4, 8, 16];
+
+/// Where a configuration error occurred.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ConfigLocation {
+    /// A line of a config file, starting at 1.
+    Line(usize),
+    /// A command-line argument.
+    Argument(String),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ConfigErrorKind {
+    /// The line isn't a valid `key = value` pair.
+    Syntax(String),
+    UnknownKey(String),
+    /// A command-line argument without a value, for a key which isn't boolean.
+    MissingValue(String),
+    /// The value has the wrong type, or is out of range.
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigErrorKind::Syntax(message) => {
+                write!(f, "{}", message)
+            }
+            ConfigErrorKind::UnknownKey(key) => {
+                write!(f, "unknown key '{}'", key)
+            }
+            ConfigErrorKind::MissingValue(key) => {
+                write!(f, "missing value for '{}'", key)
+            }
+            ConfigErrorKind::InvalidValue { key, value, expected } => {
+                write!(f, "invalid value '{}' for '{}', expected {}", value, key, expected)
+            }
+        }
+    }
+}
+
+/// An error reported by the `SAppDesc` config functions.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SAppConfigError {
+    pub location: ConfigLocation,
+    pub kind: ConfigErrorKind,
+}
+
+impl fmt::Display for SAppConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.location {
+            ConfigLocation::Line(line) => write!(f, "line {}: {}", line, self.kind),
+            ConfigLocation::Argument(arg) => write!(f, "argument '{}': {}", arg, self.kind),
+        }
+    }
+}
+
+impl error::Error for SAppConfigError {}
+
+/// A config value, typed if read from a config file.
+enum ConfigValue<'a> {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+    /// An unparsed command-line argument value.
+    Raw(&'a str),
+    /// A command-line argument without a value.
+    Flag,
+}
+
+impl<'a> ConfigValue<'a> {
+    fn as_string(&self) -> String {
+        match self {
+            ConfigValue::Integer(i) => i.to_string(),
+            ConfigValue::Boolean(b) => b.to_string(),
+            ConfigValue::String(s) => format!("{:?}", s),
+            ConfigValue::Raw(s) => s.to_string(),
+            ConfigValue::Flag => String::new(),
+        }
+    }
+}
+
+impl SAppDesc {
+    /// Creates a description from the command-line arguments of the process.
+    ///
+    /// Also returns the arguments which have not been applied, see
+    /// `apply_args()`.
+    pub fn from_args() -> Result<(SAppDesc, Vec<OsString>), SAppConfigError> {
+        let mut desc = SAppDesc::default();
+        let unknown = desc.apply_args(std::env::args_os().skip(1))?;
+        Ok((desc, unknown))
+    }
+
+    /// Creates a description from the content of a config file.
+    ///
+    /// Unknown keys are reported as errors.
+    pub fn from_toml(toml: &str) -> Result<SAppDesc, SAppConfigError> {
+        let mut desc = SAppDesc::default();
+        desc.apply_toml(toml)?;
+        Ok(desc)
+    }
+
+    /// Overrides fields with the values of command-line arguments.
+    ///
+    /// Returns the arguments which have not been applied, in order: those
+    /// which don't start with `--`, don't match a known key, or aren't
+    /// valid UTF-8.
+    pub fn apply_args<I, S>(&mut self, args: I) -> Result<Vec<OsString>, SAppConfigError>
+        where I: IntoIterator<Item=S>,
+              S: AsRef<OsStr> {
+        let mut unknown = Vec::new();
+
+        for arg in args {
+            let os_arg = arg.as_ref();
+
+            let flag = match os_arg.to_str().and_then(|arg| arg.strip_prefix("--")) {
+                Some(flag) => flag,
+                None => {
+                    unknown.push(os_arg.to_os_string());
+                    continue;
+                }
+            };
+
+            let (key, value) = match flag.find('=') {
+                Some(index) => (&flag[..index], ConfigValue::Raw(&flag[index + 1..])),
+                None => (flag, ConfigValue::Flag),
+            };
+
+            let key = key.replace('-', "_");
+            match self.set(&key, value) {
+                Ok(()) => {}
+                Err(ConfigErrorKind::UnknownKey(_)) => unknown.push(os_arg.to_os_string()),
+                Err(kind) => return Err(SAppConfigError {
+                    location: ConfigLocation::Argument(os_arg.to_string_lossy().into_owned()),
+                    kind,
+                }),
+            }
+        }
+
+        Ok(unknown)
+    }
+
+    /// Overrides fields with the values of a config file, see `from_toml()`.
+    pub fn apply_toml(&mut self, toml: &str) -> Result<(), SAppConfigError> {
+        for (index, line) in toml.lines().enumerate() {
+            self.apply_toml_line(line).map_err(|kind| SAppConfigError {
+                location: ConfigLocation::Line(index + 1),
+                kind,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn apply_toml_line(&mut self, line: &str) -> Result<(), ConfigErrorKind> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+
+        let index = line.find('=')
+            .ok_or_else(|| ConfigErrorKind::Syntax("expected 'key = value'".to_string()))?;
+
+        let key = line[..index].trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(ConfigErrorKind::Syntax(format!("invalid key '{}'", key)));
+        }
+
+        let value = parse_toml_value(line[index + 1..].trim())?;
+        self.set(&key.replace('-', "_"), value)
+    }
+
+    fn set(&mut self, key: &str, value: ConfigValue) -> Result<(), ConfigErrorKind> {
+        match key {
+            "width" => self.width = int_value(key, &value, |i| i >= 0, "a non-negative integer")?,
+            "height" => self.height = int_value(key, &value, |i| i >= 0, "a non-negative integer")?,
+            "sample_count" => {
+                self.sample_count = int_value(key, &value, |i| SAMPLE_COUNTS.contains(&i), "1, 2, 4, 8 or 16")?
+            }
+            "swap_interval" => {
+                self.swap_interval = int_value(key, &value, |i| i >= 0, "a non-negative integer")?
+            }
+            "high_dpi" => self.high_dpi = bool_value(key, &value)?,
+            "fullscreen" => self.fullscreen = bool_value(key, &value)?,
+            "alpha" => self.alpha = bool_value(key, &value)?,
+            "window_title" => self.window_title = string_value(key, value)?,
+            "user_cursor" => self.user_cursor = bool_value(key, &value)?,
+            "html5_canvas_name" => self.html5_canvas_name = string_value(key, value)?,
+            "html5_canvas_resize" => self.html5_canvas_resize = bool_value(key, &value)?,
+            "html5_preserve_drawing_buffer" => self.html5_preserve_drawing_buffer = bool_value(key, &value)?,
+            "html5_premultiplied_alpha" => self.html5_premultiplied_alpha = bool_value(key, &value)?,
+            "html5_ask_leave_site" => self.html5_ask_leave_site = bool_value(key, &value)?,
+            "ios_keyboard_resizes_canvas" => self.ios_keyboard_resizes_canvas = bool_value(key, &value)?,
+            "gl_force_gles2" => self.gl_force_gles2 = bool_value(key, &value)?,
+            _ => return Err(ConfigErrorKind::UnknownKey(key.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_value(key: &str, value: &ConfigValue, expected: &'static str) -> ConfigErrorKind {
+    match value {
+        ConfigValue::Flag => ConfigErrorKind::MissingValue(key.to_string()),
+        _ => ConfigErrorKind::InvalidValue {
+            key: key.to_string(),
+            value: value.as_string(),
+            expected,
+        },
+    }
+}
+
+fn int_value<F>(key: &str, value: &ConfigValue, valid: F, expected: &'static str) -> Result<i32, ConfigErrorKind>
+    where F: Fn(i32) -> bool {
+    let i = match value {
+        ConfigValue::Integer(i) => Some(*i),
+        ConfigValue::Raw(s) => s.parse::<i64>().ok(),
+        _ => None,
+    };
+
+    match i.and_then(|i| i32::try_from(i).ok()) {
+        Some(i) if valid(i) => Ok(i),
+        _ => Err(invalid_value(key, value, expected)),
+    }
+}
+
+fn bool_value(key: &str, value: &ConfigValue) -> Result<bool, ConfigErrorKind> {
+    match value {
+        ConfigValue::Boolean(b) => Ok(*b),
+        ConfigValue::Flag => Ok(true),
+        ConfigValue::Raw("true") | ConfigValue::Raw("1") | ConfigValue::Raw("yes") => Ok(true),
+        ConfigValue::Raw("false") | ConfigValue::Raw("0") | ConfigValue::Raw("no") => Ok(false),
+        _ => Err(invalid_value(key, value, "true or false")),
+    }
+}
+
+fn string_value(key: &str, value: ConfigValue) -> Result<String, ConfigErrorKind> {
+    match value {
+        ConfigValue::String(s) => Ok(s),
+        ConfigValue::Raw(s) => Ok(s.to_string()),
+        _ => Err(invalid_value(key, &value, "a string")),
+    }
+}
+
+/// Parses integers, booleans and basic strings, followed by an optional comment.
+fn parse_toml_value(s: &str) -> Result<ConfigValue<'static>, ConfigErrorKind> {
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut string = String::new();
+        let mut chars = rest.chars();
+
+        loop {
+            match chars.next() {
+                None => return Err(ConfigErrorKind::Syntax("unterminated string".to_string())),
+                Some('"') => break,
+                Some('\\') => {
+                    let c = match chars.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        c => return Err(ConfigErrorKind::Syntax(match c {
+                            Some(c) => format!("unsupported escape sequence '\\{}'", c),
+                            None => "unterminated string".to_string(),
+                        })),
+                    };
+                    string.push(c);
+                }
+                Some(c) => string.push(c),
+            }
+        }
+
+        expect_end_of_line(chars.as_str())?;
+        return Ok(ConfigValue::String(string));
+    }
+
+    let end = s.find('#').unwrap_or(s.len());
+    let token = s[..end].trim();
+
+    match token {
+        "" => Err(ConfigErrorKind::Syntax("missing value".to_string())),
+        "true" => Ok(ConfigValue::Boolean(true)),
+        "false" => Ok(ConfigValue::Boolean(false)),
+        _ => token.replace('_', "").parse::<i64>()
+            .map(ConfigValue::Integer)
+            .map_err(|_| ConfigErrorKind::Syntax(format!("unsupported value '{}'", token))),
+    }
+}
+
+fn expect_end_of_line(rest: &str) -> Result<(), ConfigErrorKind> {
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(ConfigErrorKind::Syntax(format!("unexpected '{}' after value", rest)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml() {
+        let desc = SAppDesc::from_toml(r#"
+            # window setup
+            width = 1_280
+            height = 720  # HD
+            high-dpi = true
+            sample_count = 4
+            window_title = "QA \"build\"" # quoted
+        "#).unwrap();
+
+        assert_eq!((desc.width, desc.height, desc.sample_count), (1280, 720, 4));
+        assert!(desc.high_dpi);
+        assert_eq!(desc.window_title, "QA \"build\"");
+    }
+
+    #[test]
+    fn toml_errors() {
+        let err = SAppDesc::from_toml("width = 640\nheigth = 480").unwrap_err();
+        assert_eq!(err, SAppConfigError {
+            location: ConfigLocation::Line(2),
+            kind: ConfigErrorKind::UnknownKey("heigth".to_string()),
+        });
+
+        let err = SAppDesc::from_toml("sample_count = 3").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid value '3' for 'sample_count', expected 1, 2, 4, 8 or 16");
+
+        let err = SAppDesc::from_toml("fullscreen = 1").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: invalid value '1' for 'fullscreen', expected true or false");
+
+        let err = SAppDesc::from_toml("window_title = \"QA").unwrap_err();
+        assert_eq!(err.kind, ConfigErrorKind::Syntax("unterminated string".to_string()));
+
+        let err = SAppDesc::from_toml("width").unwrap_err();
+        assert_eq!(err.kind, ConfigErrorKind::Syntax("expected 'key = value'".to_string()));
+    }
+
+    #[test]
+    fn args() {
+        let mut desc = SAppDesc::from_toml("width = 640\nfullscreen = true").unwrap();
+        let unknown = desc.apply_args(["--width=1024", "--high-dpi", "--fullscreen=no", "level1", "--god-mode"])
+            .unwrap();
+
+        assert_eq!(desc.width, 1024);
+        assert!(desc.high_dpi);
+        assert!(!desc.fullscreen);
+        assert_eq!(unknown, ["level1", "--god-mode"]);
+    }
+
+    #[test]
+    fn arg_errors() {
+        let err = SAppDesc::default().apply_args(["--width"]).unwrap_err();
+        assert_eq!(err.to_string(), "argument '--width': missing value for 'width'");
+
+        let err = SAppDesc::default().apply_args(["--height=-1"]).unwrap_err();
+        assert_eq!(err, SAppConfigError {
+            location: ConfigLocation::Argument("--height=-1".to_string()),
+            kind: ConfigErrorKind::InvalidValue {
+                key: "height".to_string(),
+                value: "-1".to_string(),
+                expected: "a non-negative integer",
+            },
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_args() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let arg = OsStr::from_bytes(b"--window-title=\xff");
+        let mut desc = SAppDesc::default();
+        let unknown = desc.apply_args([arg, OsStr::new("--width=800")]).unwrap();
+
+        assert_eq!(desc.width, 800);
+        assert_eq!(unknown, [arg]);
+    }
+}
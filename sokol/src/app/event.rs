@@ -0,0 +1,279 @@
+//! Typed application events.
+//!
+//! `SAppEvent` mirrors the C struct, with all fields set regardless of the
+//! event type. `SAppEvent::to_event()` converts it into an `Event`, which
+//! only carries the fields which are meaningful for its type.
+
+use super::*;
+
+/// Phase of a touch event.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TouchPhase {
+    Began,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+/// A touch point of a touch event.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Touch {
+    pub id: usize,
+    pub x: f32,
+    pub y: f32,
+    /// True if this touch point changed since the last touch event.
+    pub changed: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event {
+    KeyDown {
+        key: SAppKeycode,
+        modifiers: SAppModifier,
+        repeat: bool,
+    },
+    KeyUp {
+        key: SAppKeycode,
+        modifiers: SAppModifier,
+    },
+    Char(char),
+    MouseDown {
+        button: SAppMouseButton,
+        x: f32,
+        y: f32,
+        modifiers: SAppModifier,
+    },
+    MouseUp {
+        button: SAppMouseButton,
+        x: f32,
+        y: f32,
+        modifiers: SAppModifier,
+    },
+    MouseScroll {
+        dx: f32,
+        dy: f32,
+    },
+    MouseMove {
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
+    MouseEnter,
+    MouseLeave,
+    Touches {
+        phase: TouchPhase,
+        touches: Vec<Touch>,
+    },
+    Resized {
+        window_width: i32,
+        window_height: i32,
+        framebuffer_width: i32,
+        framebuffer_height: i32,
+    },
+    Iconified,
+    Restored,
+    Suspended,
+    Resumed,
+    UpdateCursor,
+    QuitRequested,
+}
+
+impl SAppEvent {
+    /// Creates an event of the given type, with all other fields zeroed.
+    pub fn new(event_type: SAppEventType) -> SAppEvent {
+        SAppEvent {
+            frame_count: 0,
+            event_type,
+            key_code: SAppKeycode::KeyInvalid,
+            char_code: 0,
+            key_repeat: false,
+            modifiers: SAppModifier::empty(),
+            mouse_button: SAppMouseButton::Invalid,
+            mouse_x: 0.0,
+            mouse_y: 0.0,
+            mouse_dx: 0.0,
+            mouse_dy: 0.0,
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            num_touches: 0,
+            touches: [SAppTouchPoint {
+                identifier: 0,
+                pos_x: 0.0,
+                pos_y: 0.0,
+                changed: false,
+            }; ffi::SAPP_MAX_TOUCHPOINTS],
+            window_width: 0,
+            window_height: 0,
+            framebuffer_width: 0,
+            framebuffer_height: 0,
+        }
+    }
+
+    /// Converts the event into an `Event`.
+    ///
+    /// Returns `None` for invalid events, and for `Char` events which don't
+    /// carry a valid unicode code point.
+    pub fn to_event(&self) -> Option<Event> {
+        let event = match self.event_type {
+            SAppEventType::Invalid => return None,
+            SAppEventType::KeyDown => Event::KeyDown {
+                key: self.key_code,
+                modifiers: self.modifiers,
+                repeat: self.key_repeat,
+            },
+            SAppEventType::KeyUp => Event::KeyUp {
+                key: self.key_code,
+                modifiers: self.modifiers,
+            },
+            SAppEventType::Char => Event::Char(std::char::from_u32(self.char_code)?),
+            SAppEventType::MouseDown => Event::MouseDown {
+                button: self.mouse_button,
+                x: self.mouse_x,
+                y: self.mouse_y,
+                modifiers: self.modifiers,
+            },
+            SAppEventType::MouseUp => Event::MouseUp {
+                button: self.mouse_button,
+                x: self.mouse_x,
+                y: self.mouse_y,
+                modifiers: self.modifiers,
+            },
+            SAppEventType::MouseScroll => Event::MouseScroll {
+                dx: self.scroll_x,
+                dy: self.scroll_y,
+            },
+            SAppEventType::MouseMove => Event::MouseMove {
+                x: self.mouse_x,
+                y: self.mouse_y,
+                dx: self.mouse_dx,
+                dy: self.mouse_dy,
+            },
+            SAppEventType::MouseEnter => Event::MouseEnter,
+            SAppEventType::MouseLeave => Event::MouseLeave,
+            SAppEventType::TouchesBegan => self.touch_event(TouchPhase::Began),
+            SAppEventType::TouchesMoved => self.touch_event(TouchPhase::Moved),
+            SAppEventType::TouchesEnded => self.touch_event(TouchPhase::Ended),
+            SAppEventType::TouchesCancelled => self.touch_event(TouchPhase::Cancelled),
+            SAppEventType::Resized => Event::Resized {
+                window_width: self.window_width,
+                window_height: self.window_height,
+                framebuffer_width: self.framebuffer_width,
+                framebuffer_height: self.framebuffer_height,
+            },
+            SAppEventType::Iconified => Event::Iconified,
+            SAppEventType::Restored => Event::Restored,
+            SAppEventType::Suspended => Event::Suspended,
+            SAppEventType::Resumed => Event::Resumed,
+            SAppEventType::UpdateCursor => Event::UpdateCursor,
+            SAppEventType::QuitRequested => Event::QuitRequested,
+        };
+
+        Some(event)
+    }
+
+    fn touch_event(&self, phase: TouchPhase) -> Event {
+        let num_touches = (self.num_touches.max(0) as usize).min(self.touches.len());
+
+        Event::Touches {
+            phase,
+            touches: self.touches[..num_touches].iter()
+                .map(|t| Touch {
+                    id: t.identifier,
+                    x: t.pos_x,
+                    y: t.pos_y,
+                    changed: t.changed,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn char_event(char_code: u32) -> SAppEvent {
+        SAppEvent {
+            char_code,
+            ..SAppEvent::new(SAppEventType::Char)
+        }
+    }
+
+    #[test]
+    fn chars() {
+        assert_eq!(char_event('a' as u32).to_event(), Some(Event::Char('a')));
+        assert_eq!(char_event(0x1f600).to_event(), Some(Event::Char('\u{1f600}')));
+        // surrogates and values beyond the unicode range aren't chars
+        assert_eq!(char_event(0xd800).to_event(), None);
+        assert_eq!(char_event(0x110000).to_event(), None);
+    }
+
+    #[test]
+    fn key_down() {
+        let event = SAppEvent {
+            key_code: SAppKeycode::KeyA,
+            key_repeat: true,
+            modifiers: SAppModifier::SHIFT | SAppModifier::CONTROL,
+            ..SAppEvent::new(SAppEventType::KeyDown)
+        };
+
+        assert_eq!(event.to_event(), Some(Event::KeyDown {
+            key: SAppKeycode::KeyA,
+            modifiers: SAppModifier::SHIFT | SAppModifier::CONTROL,
+            repeat: true,
+        }));
+    }
+
+    #[test]
+    fn touches() {
+        let mut event = SAppEvent::new(SAppEventType::TouchesMoved);
+        for (i, touch) in event.touches.iter_mut().enumerate() {
+            touch.identifier = i + 1;
+            touch.pos_x = i as f32;
+            touch.pos_y = 2.0 * i as f32;
+            touch.changed = i == 1;
+        }
+        event.num_touches = 2;
+
+        assert_eq!(event.to_event(), Some(Event::Touches {
+            phase: TouchPhase::Moved,
+            touches: vec![
+                Touch { id: 1, x: 0.0, y: 0.0, changed: false },
+                Touch { id: 2, x: 1.0, y: 2.0, changed: true },
+            ],
+        }));
+
+        // out-of-range counts are clamped to the touch point array
+        event.num_touches = -1;
+        assert_eq!(event.to_event(), Some(Event::Touches { phase: TouchPhase::Moved, touches: vec![] }));
+        event.num_touches = 100;
+        match event.to_event() {
+            Some(Event::Touches { touches, .. }) => assert_eq!(touches.len(), ffi::SAPP_MAX_TOUCHPOINTS),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resized() {
+        let event = SAppEvent {
+            window_width: 640,
+            window_height: 480,
+            framebuffer_width: 1280,
+            framebuffer_height: 960,
+            ..SAppEvent::new(SAppEventType::Resized)
+        };
+
+        assert_eq!(event.to_event(), Some(Event::Resized {
+            window_width: 640,
+            window_height: 480,
+            framebuffer_width: 1280,
+            framebuffer_height: 960,
+        }));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(SAppEvent::new(SAppEventType::Invalid).to_event(), None);
+    }
+}
@@ -0,0 +1,562 @@
+//! Touch gesture recognition.
+//!
+//! `GestureRecognizer` consumes touch events, and reports taps, double
+//! taps, long presses, swipes, pans, pinches and rotations. Timing is based
+//! on the time passed by the caller, in seconds, so that it can be fed with
+//! synthetic touch sequences:
+//!
+//! ```ignore
+//! fn sapp_event(&mut self, event: SAppEvent) {
+//!     let now = stm_sec(stm_now());
+//!     for gesture in self.gestures.handle_event(&event, now) {
+//!         self.on_gesture(gesture);
+//!     }
+//! }
+//!
+//! fn sapp_frame(&mut self) {
+//!     // long presses are detected without any touch events
+//!     if let Some(gesture) = self.gestures.update(stm_sec(stm_now())) {
+//!         self.on_gesture(gesture);
+//!     }
+//! }
+//! ```
+
+use std::f32::consts::PI;
+
+use super::*;
+
+/// Thresholds used by `GestureRecognizer`. Distances are in pixels,
+/// durations in seconds.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GestureConfig {
+    /// Maximum movement of a tap, a long press, and before a pan starts.
+    pub tap_max_distance: f32,
+    pub tap_max_duration: f64,
+    /// Maximum time between the end of two taps to form a double tap.
+    pub double_tap_max_interval: f64,
+    pub long_press_duration: f64,
+    pub swipe_min_distance: f32,
+    pub swipe_max_duration: f64,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            tap_max_distance: 10.0,
+            tap_max_duration: 0.3,
+            double_tap_max_interval: 0.3,
+            long_press_duration: 0.5,
+            swipe_min_distance: 50.0,
+            swipe_max_duration: 0.3,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Gesture {
+    /// A short touch without movement. The second tap of a double tap is
+    /// reported as `DoubleTap` instead.
+    Tap {
+        x: f32,
+        y: f32,
+    },
+    DoubleTap {
+        x: f32,
+        y: f32,
+    },
+    /// A touch held without movement. No `Tap` is reported once it ends.
+    LongPress {
+        x: f32,
+        y: f32,
+    },
+    /// A fast single-finger movement. Reported when the touch ends, after
+    /// the end of the pan.
+    Swipe {
+        direction: SwipeDirection,
+        dx: f32,
+        dy: f32,
+    },
+    /// A single-finger movement. `dx` and `dy` are relative to the last
+    /// `Pan` gesture.
+    Pan {
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    },
+    /// A change of the distance between two fingers. `scale` is relative to
+    /// the last `Pinch` gesture. `x` and `y` are the center between the fingers.
+    Pinch {
+        scale: f32,
+        x: f32,
+        y: f32,
+    },
+    /// A rotation of two fingers. `angle` is in radians, relative to the last
+    /// `Rotate` gesture, and positive in the direction from +x to +y.
+    Rotate {
+        angle: f32,
+        x: f32,
+        y: f32,
+    },
+}
+
+#[derive(Copy, Clone, Debug)]
+struct TrackedTouch {
+    id: usize,
+    x: f32,
+    y: f32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SingleTouch {
+    id: usize,
+    start_x: f32,
+    start_y: f32,
+    start_time: f64,
+    last_x: f32,
+    last_y: f32,
+    long_pressed: bool,
+    panning: bool,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct LastTap {
+    x: f32,
+    y: f32,
+    time: f64,
+}
+
+/// Recognizes gestures from touch events.
+#[derive(Clone, Debug)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    touches: Vec<TrackedTouch>,
+    single: Option<SingleTouch>,
+    // distance and angle between the first two touches
+    two_finger: Option<(f32, f32)>,
+    last_tap: Option<LastTap>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        GestureRecognizer::new(Default::default())
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> GestureRecognizer {
+        GestureRecognizer {
+            config,
+            touches: Vec::new(),
+            single: None,
+            two_finger: None,
+            last_tap: None,
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Detects long presses. Call this once per frame.
+    pub fn update(&mut self, time: f64) -> Option<Gesture> {
+        let config = self.config;
+        let single = self.single.as_mut()?;
+
+        if single.long_pressed || single.panning
+            || time - single.start_time < config.long_press_duration {
+            return None;
+        }
+
+        single.long_pressed = true;
+        Some(Gesture::LongPress {
+            x: single.start_x,
+            y: single.start_y,
+        })
+    }
+
+    /// Updates the recognizer from an application event, and returns the
+    /// gestures detected. Events other than touch events are ignored.
+    pub fn handle_event(&mut self, event: &SAppEvent, time: f64) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+
+        let phase = match event.event_type {
+            SAppEventType::TouchesBegan => TouchPhase::Began,
+            SAppEventType::TouchesMoved => TouchPhase::Moved,
+            SAppEventType::TouchesEnded => TouchPhase::Ended,
+            SAppEventType::TouchesCancelled => TouchPhase::Cancelled,
+            _ => return gestures,
+        };
+
+        gestures.extend(self.update(time));
+
+        let num_touches = (event.num_touches.max(0) as usize).min(event.touches.len());
+        let points = &event.touches[..num_touches];
+
+        match phase {
+            TouchPhase::Began => self.touches_began(points, time, &mut gestures),
+            TouchPhase::Moved => self.touches_moved(points, &mut gestures),
+            TouchPhase::Ended => self.touches_ended(points, time, &mut gestures),
+            TouchPhase::Cancelled => self.touches_cancelled(&mut gestures),
+        }
+
+        gestures
+    }
+
+    fn touches_began(&mut self, points: &[SAppTouchPoint], time: f64, gestures: &mut Vec<Gesture>) {
+        for p in points.iter().filter(|p| p.changed) {
+            if !self.touches.iter().any(|t| t.id == p.identifier) {
+                self.touches.push(TrackedTouch {
+                    id: p.identifier,
+                    x: p.pos_x,
+                    y: p.pos_y,
+                });
+            }
+        }
+
+        match self.touches.len() {
+            0 => {}
+            1 => {
+                let t = self.touches[0];
+                self.single = Some(SingleTouch {
+                    id: t.id,
+                    start_x: t.x,
+                    start_y: t.y,
+                    start_time: time,
+                    last_x: t.x,
+                    last_y: t.y,
+                    long_pressed: false,
+                    panning: false,
+                });
+            }
+            _ => {
+                // a second finger ends all single-finger gestures
+                self.end_single(TouchPhase::Cancelled, gestures);
+                if self.two_finger.is_none() {
+                    self.two_finger = Some(self.two_finger_metrics());
+                }
+            }
+        }
+    }
+
+    fn touches_moved(&mut self, points: &[SAppTouchPoint], gestures: &mut Vec<Gesture>) {
+        for p in points.iter() {
+            if let Some(t) = self.touches.iter_mut().find(|t| t.id == p.identifier) {
+                t.x = p.pos_x;
+                t.y = p.pos_y;
+            }
+        }
+
+        if let Some((last_distance, last_angle)) = self.two_finger {
+            let (distance, angle) = self.two_finger_metrics();
+            let (x, y) = self.two_finger_center();
+
+            if last_distance > 0.0 && distance != last_distance {
+                gestures.push(Gesture::Pinch {
+                    scale: distance / last_distance,
+                    x,
+                    y,
+                });
+            }
+
+            let mut delta = angle - last_angle;
+            if delta > PI {
+                delta -= 2.0 * PI;
+            } else if delta <= -PI {
+                delta += 2.0 * PI;
+            }
+            if delta != 0.0 {
+                gestures.push(Gesture::Rotate {
+                    angle: delta,
+                    x,
+                    y,
+                });
+            }
+
+            self.two_finger = Some((distance, angle));
+            return;
+        }
+
+        let tap_max_distance = self.config.tap_max_distance;
+        let t = match self.single.and_then(|s| self.touches.iter().find(|t| t.id == s.id)) {
+            Some(t) => *t,
+            None => return,
+        };
+        let single = match self.single.as_mut() {
+            Some(single) => single,
+            None => return,
+        };
+
+        if !single.panning {
+            if single.long_pressed || distance(single.start_x, single.start_y, t.x, t.y) <= tap_max_distance {
+                return;
+            }
+            single.panning = true;
+            gestures.push(Gesture::Pan {
+                phase: TouchPhase::Began,
+                x: single.start_x,
+                y: single.start_y,
+                dx: 0.0,
+                dy: 0.0,
+            });
+            single.last_x = single.start_x;
+            single.last_y = single.start_y;
+        }
+
+        gestures.push(Gesture::Pan {
+            phase: TouchPhase::Moved,
+            x: t.x,
+            y: t.y,
+            dx: t.x - single.last_x,
+            dy: t.y - single.last_y,
+        });
+        single.last_x = t.x;
+        single.last_y = t.y;
+    }
+
+    fn touches_ended(&mut self, points: &[SAppTouchPoint], time: f64, gestures: &mut Vec<Gesture>) {
+        for p in points.iter().filter(|p| p.changed) {
+            if let Some(index) = self.touches.iter().position(|t| t.id == p.identifier) {
+                let t = self.touches.remove(index);
+
+                let is_single = self.single.is_some_and(|s| s.id == t.id);
+                if is_single {
+                    self.single_ended(p.pos_x, p.pos_y, time, gestures);
+                }
+            }
+        }
+
+        if self.touches.len() < 2 {
+            self.two_finger = None;
+        }
+    }
+
+    fn touches_cancelled(&mut self, gestures: &mut Vec<Gesture>) {
+        self.end_single(TouchPhase::Cancelled, gestures);
+        self.touches.clear();
+        self.two_finger = None;
+    }
+
+    fn single_ended(&mut self, x: f32, y: f32, time: f64, gestures: &mut Vec<Gesture>) {
+        let single = match self.single.take() {
+            Some(single) => single,
+            None => return,
+        };
+
+        let config = self.config;
+        let dx = x - single.start_x;
+        let dy = y - single.start_y;
+        let duration = time - single.start_time;
+
+        if single.panning {
+            gestures.push(Gesture::Pan {
+                phase: TouchPhase::Ended,
+                x,
+                y,
+                dx: x - single.last_x,
+                dy: y - single.last_y,
+            });
+
+            if duration <= config.swipe_max_duration && distance(0.0, 0.0, dx, dy) >= config.swipe_min_distance {
+                let direction = if dx.abs() > dy.abs() {
+                    if dx > 0.0 { SwipeDirection::Right } else { SwipeDirection::Left }
+                } else if dy > 0.0 {
+                    SwipeDirection::Down
+                } else {
+                    SwipeDirection::Up
+                };
+                gestures.push(Gesture::Swipe {
+                    direction,
+                    dx,
+                    dy,
+                });
+            }
+        } else if !single.long_pressed && duration <= config.tap_max_duration
+            && distance(0.0, 0.0, dx, dy) <= config.tap_max_distance {
+            let double_tap = self.last_tap.is_some_and(|tap| {
+                time - tap.time <= config.double_tap_max_interval
+                    && distance(tap.x, tap.y, single.start_x, single.start_y) <= config.tap_max_distance
+            });
+
+            if double_tap {
+                self.last_tap = None;
+                gestures.push(Gesture::DoubleTap {
+                    x: single.start_x,
+                    y: single.start_y,
+                });
+            } else {
+                self.last_tap = Some(LastTap {
+                    x: single.start_x,
+                    y: single.start_y,
+                    time,
+                });
+                gestures.push(Gesture::Tap {
+                    x: single.start_x,
+                    y: single.start_y,
+                });
+            }
+        }
+    }
+
+    fn end_single(&mut self, phase: TouchPhase, gestures: &mut Vec<Gesture>) {
+        if let Some(single) = self.single.take() {
+            if single.panning {
+                gestures.push(Gesture::Pan {
+                    phase,
+                    x: single.last_x,
+                    y: single.last_y,
+                    dx: 0.0,
+                    dy: 0.0,
+                });
+            }
+        }
+    }
+
+    fn two_finger_metrics(&self) -> (f32, f32) {
+        let (a, b) = (self.touches[0], self.touches[1]);
+        (distance(a.x, a.y, b.x, b.y), (b.y - a.y).atan2(b.x - a.x))
+    }
+
+    fn two_finger_center(&self) -> (f32, f32) {
+        let (a, b) = (self.touches[0], self.touches[1]);
+        ((a.x + b.x) * 0.5, (a.y + b.y) * 0.5)
+    }
+}
+
+fn distance(x0: f32, y0: f32, x1: f32, y1: f32) -> f32 {
+    ((x1 - x0) * (x1 - x0) + (y1 - y0) * (y1 - y0)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn touches(event_type: SAppEventType, points: &[(usize, f32, f32)]) -> SAppEvent {
+        let mut event = SAppEvent::new(event_type);
+        event.num_touches = points.len() as i32;
+        for (touch, &(identifier, x, y)) in event.touches.iter_mut().zip(points.iter()) {
+            touch.identifier = identifier;
+            touch.pos_x = x;
+            touch.pos_y = y;
+            touch.changed = true;
+        }
+        event
+    }
+
+    fn began(points: &[(usize, f32, f32)]) -> SAppEvent {
+        touches(SAppEventType::TouchesBegan, points)
+    }
+
+    fn moved(points: &[(usize, f32, f32)]) -> SAppEvent {
+        touches(SAppEventType::TouchesMoved, points)
+    }
+
+    fn ended(points: &[(usize, f32, f32)]) -> SAppEvent {
+        touches(SAppEventType::TouchesEnded, points)
+    }
+
+    #[test]
+    fn tap() {
+        let mut gestures = GestureRecognizer::default();
+        assert_eq!(gestures.handle_event(&began(&[(1, 100.0, 50.0)]), 0.0), []);
+        assert_eq!(gestures.handle_event(&moved(&[(1, 103.0, 52.0)]), 0.05), []);
+        assert_eq!(gestures.handle_event(&ended(&[(1, 103.0, 52.0)]), 0.1),
+                   [Gesture::Tap { x: 100.0, y: 50.0 }]);
+    }
+
+    #[test]
+    fn no_tap_if_ended_far_away() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 100.0, 50.0)]), 0.0);
+        assert_eq!(gestures.handle_event(&ended(&[(1, 150.0, 50.0)]), 0.1), []);
+    }
+
+    #[test]
+    fn no_tap_if_held_too_long() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 100.0, 50.0)]), 0.0);
+        assert_eq!(gestures.handle_event(&ended(&[(1, 100.0, 50.0)]), 0.4), []);
+    }
+
+    #[test]
+    fn double_tap() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 100.0, 50.0)]), 0.0);
+        assert_eq!(gestures.handle_event(&ended(&[(1, 100.0, 50.0)]), 0.1),
+                   [Gesture::Tap { x: 100.0, y: 50.0 }]);
+        gestures.handle_event(&began(&[(2, 104.0, 50.0)]), 0.2);
+        assert_eq!(gestures.handle_event(&ended(&[(2, 104.0, 50.0)]), 0.3),
+                   [Gesture::DoubleTap { x: 104.0, y: 50.0 }]);
+
+        // a third tap starts over
+        gestures.handle_event(&began(&[(3, 104.0, 50.0)]), 0.4);
+        assert_eq!(gestures.handle_event(&ended(&[(3, 104.0, 50.0)]), 0.5),
+                   [Gesture::Tap { x: 104.0, y: 50.0 }]);
+    }
+
+    #[test]
+    fn long_press() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 100.0, 50.0)]), 0.0);
+        assert_eq!(gestures.update(0.4), None);
+        assert_eq!(gestures.update(0.6), Some(Gesture::LongPress { x: 100.0, y: 50.0 }));
+        assert_eq!(gestures.update(0.7), None);
+        assert_eq!(gestures.handle_event(&ended(&[(1, 100.0, 50.0)]), 0.8), []);
+    }
+
+    #[test]
+    fn swipe() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 0.0, 0.0)]), 0.0);
+        assert_eq!(gestures.handle_event(&moved(&[(1, 30.0, 0.0)]), 0.05), [
+            Gesture::Pan { phase: TouchPhase::Began, x: 0.0, y: 0.0, dx: 0.0, dy: 0.0 },
+            Gesture::Pan { phase: TouchPhase::Moved, x: 30.0, y: 0.0, dx: 30.0, dy: 0.0 },
+        ]);
+        assert_eq!(gestures.handle_event(&ended(&[(1, 0.0, 100.0)]), 0.15), [
+            Gesture::Pan { phase: TouchPhase::Ended, x: 0.0, y: 100.0, dx: -30.0, dy: 100.0 },
+            Gesture::Swipe { direction: SwipeDirection::Down, dx: 0.0, dy: 100.0 },
+        ]);
+    }
+
+    #[test]
+    fn pan() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 0.0, 0.0)]), 0.0);
+        assert_eq!(gestures.handle_event(&moved(&[(1, 0.0, -20.0)]), 0.2).len(), 2);
+        assert_eq!(gestures.handle_event(&moved(&[(1, 5.0, -60.0)]), 0.5),
+                   [Gesture::Pan { phase: TouchPhase::Moved, x: 5.0, y: -60.0, dx: 5.0, dy: -40.0 }]);
+
+        // too slow for a swipe
+        assert_eq!(gestures.handle_event(&ended(&[(1, 5.0, -80.0)]), 1.0),
+                   [Gesture::Pan { phase: TouchPhase::Ended, x: 5.0, y: -80.0, dx: 0.0, dy: -20.0 }]);
+
+        // and no long press while panning
+        assert_eq!(gestures.update(2.0), None);
+    }
+
+    #[test]
+    fn pinch_and_rotate() {
+        let mut gestures = GestureRecognizer::default();
+        gestures.handle_event(&began(&[(1, 0.0, 0.0)]), 0.0);
+        gestures.handle_event(&began(&[(2, 100.0, 0.0)]), 0.05);
+
+        assert_eq!(gestures.handle_event(&moved(&[(1, 0.0, 0.0), (2, 200.0, 0.0)]), 0.1),
+                   [Gesture::Pinch { scale: 2.0, x: 100.0, y: 0.0 }]);
+        assert_eq!(gestures.handle_event(&moved(&[(1, 0.0, 0.0), (2, 0.0, 200.0)]), 0.2),
+                   [Gesture::Rotate { angle: FRAC_PI_2, x: 0.0, y: 100.0 }]);
+
+        // lifting both fingers is no tap
+        assert_eq!(gestures.handle_event(&ended(&[(1, 0.0, 0.0), (2, 0.0, 200.0)]), 0.25), []);
+    }
+}
@@ -0,0 +1,380 @@
+//! Headless application driver.
+//!
+//! `Headless` calls the `SApp` callbacks directly, without `sapp_run()`
+//! opening a window. While it is alive, `sapp_width()`, `sapp_height()`,
+//! `sapp_dpi_scale()`, `sapp_frame_count()`, the keyboard, mouse and quit
+//! functions report and update simulated values instead of calling into
+//! sokol_app.
+//!
+//! Applications which call `sg_setup()` need to be compiled with the
+//! `backend-dummy` feature.
+//!
+//! ```ignore
+//! let mut headless = Headless::new(MyApp::default(), &SAppDesc {
+//!     width: 320,
+//!     height: 200,
+//!     ..Default::default()
+//! });
+//!
+//! headless.init();
+//! headless.frames(10);
+//! headless.event(key_down_event);
+//! headless.frame();
+//! let app = headless.cleanup();
+//! ```
+
+use std::cell::Cell;
+
+use super::*;
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct HeadlessState {
+    pub(super) window_width: i32,
+    pub(super) window_height: i32,
+    pub(super) dpi_scale: f32,
+    pub(super) high_dpi: bool,
+    pub(super) frame_count: u64,
+    pub(super) keyboard_shown: bool,
+    pub(super) mouse_shown: bool,
+    pub(super) mouse_cursor: SAppMouseCursor,
+    pub(super) mouse_locked: bool,
+    pub(super) quit_requested: bool,
+    pub(super) quit_ordered: bool,
+}
+
+impl HeadlessState {
+    pub(super) fn framebuffer_width(&self) -> i32 {
+        self.scale(self.window_width)
+    }
+
+    pub(super) fn framebuffer_height(&self) -> i32 {
+        self.scale(self.window_height)
+    }
+
+    fn scale(&self, size: i32) -> i32 {
+        if self.high_dpi {
+            (size as f32 * self.dpi_scale) as i32
+        } else {
+            size
+        }
+    }
+}
+
+thread_local! {
+    static HEADLESS_STATE: Cell<Option<HeadlessState>> = const { Cell::new(None) };
+}
+
+/// Returns the simulated application state, if a `Headless` driver is alive.
+pub(super) fn headless_state() -> Option<HeadlessState> {
+    HEADLESS_STATE.with(|state| state.get())
+}
+
+/// Updates the simulated application state. Returns false if there's no
+/// `Headless` driver alive.
+pub(super) fn update_headless_state<F: FnOnce(&mut HeadlessState)>(f: F) -> bool {
+    HEADLESS_STATE.with(|cell| {
+        match cell.get() {
+            Some(mut state) => {
+                f(&mut state);
+                cell.set(Some(state));
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Passes on a panic caught in a gfx trace hook during an application callback.
+fn resume_trace_hooks_panic() {
+    if let Some(payload) = crate::gfx::take_trace_hooks_panic() {
+        std::panic::resume_unwind(payload);
+    }
+}
+
+struct HeadlessGuard;
+
+impl Drop for HeadlessGuard {
+    fn drop(&mut self) {
+        HEADLESS_STATE.with(|state| state.set(None));
+    }
+}
+
+/// Runs an `SApp` implementation without a window.
+///
+/// Only one driver can be alive per thread.
+pub struct Headless<S: SApp> {
+    app: S,
+    mouse_pos: Option<(f32, f32)>,
+    _guard: HeadlessGuard,
+}
+
+impl<S: SApp> Headless<S> {
+    /// Creates the driver. Window size and `high_dpi` are taken from `desc`,
+    /// with the same defaults as sokol_app. The DPI scale of high-DPI
+    /// applications defaults to 2.0.
+    ///
+    /// Panics if another `Headless` driver is alive on this thread.
+    pub fn new(app: S, desc: &SAppDesc) -> Headless<S> {
+        HEADLESS_STATE.with(|state| {
+            assert!(state.get().is_none(), "a Headless driver is already alive on this thread");
+
+            state.set(Some(HeadlessState {
+                window_width: if desc.width > 0 { desc.width } else { 640 },
+                window_height: if desc.height > 0 { desc.height } else { 480 },
+                dpi_scale: if desc.high_dpi { 2.0 } else { 1.0 },
+                high_dpi: desc.high_dpi,
+                frame_count: 0,
+                keyboard_shown: false,
+                mouse_shown: true,
+                mouse_cursor: SAppMouseCursor::Default,
+                mouse_locked: false,
+                quit_requested: false,
+                quit_ordered: false,
+            }));
+        });
+
+        Headless {
+            app,
+            mouse_pos: None,
+            _guard: HeadlessGuard,
+        }
+    }
+
+    pub fn app(&self) -> &S {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut S {
+        &mut self.app
+    }
+
+    /// Calls `SApp::sapp_init()`.
+    pub fn init(&mut self) {
+        self.app.sapp_init();
+        resume_trace_hooks_panic();
+    }
+
+    /// Calls `SApp::sapp_frame()`, then advances the frame count.
+    ///
+    /// If `sapp_request_quit()` was called, a `QuitRequested` event is sent
+    /// afterwards, like sokol_app does.
+    pub fn frame(&mut self) {
+        self.app.sapp_frame();
+        resume_trace_hooks_panic();
+        update_headless_state(|state| state.frame_count += 1);
+
+        let quit_requested = headless_state().is_some_and(|state| state.quit_requested);
+        if quit_requested {
+            self.event(SAppEvent::new(SAppEventType::QuitRequested));
+            update_headless_state(|state| {
+                // not cancelled by the event handler
+                if state.quit_requested {
+                    state.quit_ordered = true;
+                }
+            });
+        }
+    }
+
+    /// Runs up to `num_frames` frames, and stops early if the application
+    /// quits. Returns the number of frames run.
+    pub fn frames(&mut self, num_frames: usize) -> usize {
+        for i in 0..num_frames {
+            if self.quit_ordered() {
+                return i;
+            }
+            self.frame();
+        }
+        num_frames
+    }
+
+    /// Calls `SApp::sapp_event()`. The frame count and the window and
+    /// framebuffer sizes of the event are set to the simulated values.
+    ///
+    /// Like sokol_app, `mouse_dx` and `mouse_dy` of `MouseMove` events are
+    /// computed from the previous mouse position, unless the event already
+    /// has a delta or the mouse is locked.
+    pub fn event(&mut self, mut event: SAppEvent) {
+        if let Some(state) = headless_state() {
+            event.frame_count = state.frame_count;
+            event.window_width = state.window_width;
+            event.window_height = state.window_height;
+            event.framebuffer_width = state.framebuffer_width();
+            event.framebuffer_height = state.framebuffer_height();
+
+            match event.event_type {
+                SAppEventType::MouseEnter => self.mouse_pos = None,
+                SAppEventType::MouseMove => {
+                    let no_delta = event.mouse_dx == 0.0 && event.mouse_dy == 0.0;
+                    if no_delta && !state.mouse_locked {
+                        if let Some((x, y)) = self.mouse_pos {
+                            event.mouse_dx = event.mouse_x - x;
+                            event.mouse_dy = event.mouse_y - y;
+                        }
+                    }
+                    self.mouse_pos = Some((event.mouse_x, event.mouse_y));
+                }
+                _ => {}
+            }
+        }
+        self.app.sapp_event(event);
+        resume_trace_hooks_panic();
+    }
+
+    /// Changes the window size, and sends a `Resized` event.
+    pub fn resize(&mut self, width: i32, height: i32) {
+        update_headless_state(|state| {
+            state.window_width = width;
+            state.window_height = height;
+        });
+        self.event(SAppEvent::new(SAppEventType::Resized));
+    }
+
+    /// Changes the DPI scale. This only affects the framebuffer size if
+    /// `SAppDesc::high_dpi` was set.
+    pub fn set_dpi_scale(&mut self, dpi_scale: f32) {
+        update_headless_state(|state| state.dpi_scale = dpi_scale);
+    }
+
+    /// Returns true once the application called `sapp_quit()`, or didn't
+    /// cancel a `sapp_request_quit()`.
+    pub fn quit_ordered(&self) -> bool {
+        headless_state().is_some_and(|state| state.quit_ordered)
+    }
+
+    /// Calls `SApp::sapp_cleanup()`, and returns the application.
+    pub fn cleanup(mut self) -> S {
+        self.app.sapp_cleanup();
+        resume_trace_hooks_panic();
+        self.app
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestApp {
+        initialized: bool,
+        frames: Vec<(u64, i32, i32)>,
+        events: Vec<Option<Event>>,
+        quit_frame: Option<u64>,
+        cancel_quit: bool,
+    }
+
+    impl SApp for TestApp {
+        fn sapp_init(&mut self) {
+            self.initialized = true;
+        }
+
+        fn sapp_frame(&mut self) {
+            self.frames.push((sapp_frame_count(), sapp_width(), sapp_height()));
+            if self.quit_frame == Some(sapp_frame_count()) {
+                sapp_request_quit();
+            }
+        }
+
+        fn sapp_cleanup(&mut self) {}
+
+        fn sapp_event(&mut self, event: SAppEvent) {
+            if event.event_type == SAppEventType::QuitRequested && self.cancel_quit {
+                self.cancel_quit = false;
+                sapp_cancel_quit();
+            }
+            self.events.push(event.to_event());
+        }
+    }
+
+    #[test]
+    fn frames_and_resize() {
+        let mut headless = Headless::new(TestApp::default(), &SAppDesc {
+            width: 320,
+            height: 200,
+            high_dpi: true,
+            ..Default::default()
+        });
+
+        headless.init();
+        assert!(headless.app().initialized);
+        assert_eq!(headless.frames(2), 2);
+
+        headless.resize(100, 50);
+        headless.set_dpi_scale(1.5);
+        headless.frame();
+        assert!(!headless.quit_ordered());
+
+        let app = headless.cleanup();
+        assert_eq!(app.frames, vec![(0, 640, 400), (1, 640, 400), (2, 150, 75)]);
+        assert_eq!(app.events, vec![Some(Event::Resized {
+            window_width: 100,
+            window_height: 50,
+            framebuffer_width: 200,
+            framebuffer_height: 100,
+        })]);
+
+        // sokol_app is no longer simulated once the driver is dropped
+        assert!(headless_state().is_none());
+    }
+
+    #[test]
+    fn quit() {
+        let mut headless = Headless::new(TestApp {
+            quit_frame: Some(1),
+            cancel_quit: true,
+            ..Default::default()
+        }, &SAppDesc::default());
+
+        // the first request is cancelled by the application
+        assert_eq!(headless.frames(3), 3);
+        assert!(!headless.quit_ordered());
+
+        headless.app_mut().quit_frame = Some(4);
+        assert_eq!(headless.frames(10), 2);
+        assert!(headless.quit_ordered());
+
+        let app = headless.cleanup();
+        assert_eq!(app.frames.len(), 5);
+        assert_eq!(app.events, vec![Some(Event::QuitRequested), Some(Event::QuitRequested)]);
+    }
+
+    #[test]
+    fn mouse_delta() {
+        let mut headless = Headless::new(TestApp::default(), &SAppDesc::default());
+
+        let mouse_move = |x, y, dx, dy| SAppEvent {
+            mouse_x: x,
+            mouse_y: y,
+            mouse_dx: dx,
+            mouse_dy: dy,
+            ..SAppEvent::new(SAppEventType::MouseMove)
+        };
+
+        headless.event(mouse_move(10.0, 20.0, 0.0, 0.0));
+        headless.event(mouse_move(15.0, 18.0, 0.0, 0.0));
+        // deltas of the event are kept
+        headless.event(mouse_move(16.0, 18.0, 4.0, 0.0));
+        // no delta while the pointer is locked, and after re-entering
+        sapp_lock_mouse(true);
+        headless.event(mouse_move(30.0, 30.0, 0.0, 0.0));
+        sapp_lock_mouse(false);
+        headless.event(SAppEvent::new(SAppEventType::MouseEnter));
+        headless.event(mouse_move(0.0, 0.0, 0.0, 0.0));
+
+        let app = headless.cleanup();
+        assert_eq!(app.events, vec![
+            Some(Event::MouseMove { x: 10.0, y: 20.0, dx: 0.0, dy: 0.0 }),
+            Some(Event::MouseMove { x: 15.0, y: 18.0, dx: 5.0, dy: -2.0 }),
+            Some(Event::MouseMove { x: 16.0, y: 18.0, dx: 4.0, dy: 0.0 }),
+            Some(Event::MouseMove { x: 30.0, y: 30.0, dx: 0.0, dy: 0.0 }),
+            Some(Event::MouseEnter),
+            Some(Event::MouseMove { x: 0.0, y: 0.0, dx: 0.0, dy: 0.0 }),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already alive")]
+    fn one_driver_per_thread() {
+        let _first = Headless::new(TestApp::default(), &SAppDesc::default());
+        let _second = Headless::new(TestApp::default(), &SAppDesc::default());
+    }
+}
@@ -0,0 +1,476 @@
+//! Keyboard, mouse and touch input state.
+//!
+//! `InputState` is fed with all events received by `SApp::sapp_event()`, and
+//! can then be queried from `SApp::sapp_frame()`:
+//!
+//! ```ignore
+//! fn sapp_event(&mut self, event: SAppEvent) {
+//!     self.input.handle_event(&event);
+//! }
+//!
+//! fn sapp_frame(&mut self) {
+//!     if self.input.key_pressed(SAppKeycode::KeySpace) {
+//!         self.jump();
+//!     }
+//! }
+//! ```
+
+use super::*;
+
+const DOWN: u8 = 0x01;
+const PRESSED: u8 = 0x02;
+const RELEASED: u8 = 0x04;
+const REPEATED: u8 = 0x08;
+
+const EDGES: u8 = PRESSED | RELEASED | REPEATED;
+
+/// Tracks the state of keys, mouse buttons and touches.
+///
+/// Besides the "held" state, keys and mouse buttons report if they have been
+/// pressed or released during the current frame. Mouse movement and scrolling
+/// is accumulated per frame.
+///
+/// Per-frame state is reset with the first event of a new frame, and is
+/// only reported while `sapp_frame_count()` matches the frame of the events
+/// last handled.
+pub struct InputState {
+    frame_count: u64,
+    keys: [u8; ffi::SAPP_MAX_KEYCODES],
+    mouse_buttons: [u8; ffi::SAPP_MAX_MOUSEBUTTONS],
+    modifiers: SAppModifier,
+    mouse_pos: Option<(f32, f32)>,
+    mouse_delta: (f32, f32),
+    scroll: (f32, f32),
+    touches: Vec<Touch>,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        InputState::new()
+    }
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState {
+            frame_count: 0,
+            keys: [0; ffi::SAPP_MAX_KEYCODES],
+            mouse_buttons: [0; ffi::SAPP_MAX_MOUSEBUTTONS],
+            modifiers: SAppModifier::empty(),
+            mouse_pos: None,
+            mouse_delta: (0.0, 0.0),
+            scroll: (0.0, 0.0),
+            touches: Vec::new(),
+        }
+    }
+
+    /// Updates the input state from an application event.
+    ///
+    /// All keys and mouse buttons are released if the application is
+    /// iconified or suspended, because their "up" events may never arrive.
+    pub fn handle_event(&mut self, event: &SAppEvent) {
+        self.begin_frame(event.frame_count);
+
+        match event.event_type {
+            SAppEventType::KeyDown => {
+                self.modifiers = event.modifiers;
+                if let Some(flags) = self.keys.get_mut(event.key_code as usize) {
+                    if event.key_repeat || *flags & DOWN != 0 {
+                        *flags |= REPEATED;
+                    } else {
+                        *flags |= DOWN | PRESSED;
+                    }
+                }
+            }
+            SAppEventType::KeyUp => {
+                self.modifiers = event.modifiers;
+                if let Some(flags) = self.keys.get_mut(event.key_code as usize) {
+                    release(flags);
+                }
+            }
+            SAppEventType::MouseDown | SAppEventType::MouseUp => {
+                self.modifiers = event.modifiers;
+                self.mouse_pos = Some((event.mouse_x, event.mouse_y));
+                if let Some(flags) = self.mouse_button_flags_mut(event.mouse_button) {
+                    if event.event_type == SAppEventType::MouseDown {
+                        *flags |= DOWN | PRESSED;
+                    } else {
+                        release(flags);
+                    }
+                }
+            }
+            SAppEventType::MouseMove => {
+                // relative movement is reported by the event, which also
+                // works while the pointer is locked
+                self.mouse_pos = Some((event.mouse_x, event.mouse_y));
+                self.mouse_delta.0 += event.mouse_dx;
+                self.mouse_delta.1 += event.mouse_dy;
+            }
+            SAppEventType::MouseScroll => {
+                self.scroll.0 += event.scroll_x;
+                self.scroll.1 += event.scroll_y;
+            }
+            SAppEventType::MouseLeave => {
+                // unknown until the mouse re-enters the window
+                self.mouse_pos = None;
+            }
+            SAppEventType::TouchesBegan | SAppEventType::TouchesMoved => {
+                for t in changed_touches(event) {
+                    match self.touches.iter_mut().find(|active| active.id == t.identifier) {
+                        Some(active) => {
+                            active.x = t.pos_x;
+                            active.y = t.pos_y;
+                        }
+                        None => self.touches.push(Touch {
+                            id: t.identifier,
+                            x: t.pos_x,
+                            y: t.pos_y,
+                            changed: true,
+                        }),
+                    }
+                }
+                self.mark_changed_touches(event);
+            }
+            SAppEventType::TouchesEnded | SAppEventType::TouchesCancelled => {
+                for t in changed_touches(event) {
+                    self.touches.retain(|active| active.id != t.identifier);
+                }
+                self.mark_changed_touches(event);
+            }
+            SAppEventType::Iconified | SAppEventType::Suspended => {
+                self.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns true if the key is held down.
+    pub fn key_down(&self, key: SAppKeycode) -> bool {
+        self.keys.get(key as usize).is_some_and(|flags| flags & DOWN != 0)
+    }
+
+    /// Returns true if the key has been pressed during the current frame.
+    pub fn key_pressed(&self, key: SAppKeycode) -> bool {
+        self.key_edge(key, PRESSED)
+    }
+
+    /// Returns true if the key has been released during the current frame.
+    pub fn key_released(&self, key: SAppKeycode) -> bool {
+        self.key_edge(key, RELEASED)
+    }
+
+    /// Returns true if the key sent a repeat event during the current frame.
+    pub fn key_repeated(&self, key: SAppKeycode) -> bool {
+        self.key_edge(key, REPEATED)
+    }
+
+    /// Returns true if the mouse button is held down.
+    pub fn mouse_button_down(&self, button: SAppMouseButton) -> bool {
+        self.mouse_button_flags(button) & DOWN != 0
+    }
+
+    /// Returns true if the mouse button has been pressed during the current frame.
+    pub fn mouse_button_pressed(&self, button: SAppMouseButton) -> bool {
+        self.is_current() && self.mouse_button_flags(button) & PRESSED != 0
+    }
+
+    /// Returns true if the mouse button has been released during the current frame.
+    pub fn mouse_button_released(&self, button: SAppMouseButton) -> bool {
+        self.is_current() && self.mouse_button_flags(button) & RELEASED != 0
+    }
+
+    /// The modifier keys held down with the last key or mouse button event.
+    pub fn modifiers(&self) -> SAppModifier {
+        self.modifiers
+    }
+
+    /// The last known mouse position, or `None` if the mouse is outside
+    /// the window.
+    pub fn mouse_pos(&self) -> Option<(f32, f32)> {
+        self.mouse_pos
+    }
+
+    /// The distance the mouse moved during the current frame.
+    pub fn mouse_delta(&self) -> (f32, f32) {
+        if self.is_current() { self.mouse_delta } else { (0.0, 0.0) }
+    }
+
+    /// The scroll distance accumulated during the current frame.
+    pub fn scroll(&self) -> (f32, f32) {
+        if self.is_current() { self.scroll } else { (0.0, 0.0) }
+    }
+
+    /// The active touch points. `Touch::changed` is set for touch points
+    /// which began or moved with the last touch event.
+    pub fn touches(&self) -> &[Touch] {
+        &self.touches
+    }
+
+    fn begin_frame(&mut self, frame_count: u64) {
+        if frame_count == self.frame_count {
+            return;
+        }
+
+        self.frame_count = frame_count;
+
+        for flags in self.keys.iter_mut().chain(self.mouse_buttons.iter_mut()) {
+            *flags &= !EDGES;
+        }
+
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll = (0.0, 0.0);
+    }
+
+    fn is_current(&self) -> bool {
+        self.frame_count == sapp_frame_count()
+    }
+
+    fn key_edge(&self, key: SAppKeycode, edge: u8) -> bool {
+        self.is_current() && self.keys.get(key as usize).is_some_and(|flags| flags & edge != 0)
+    }
+
+    fn mouse_button_flags(&self, button: SAppMouseButton) -> u8 {
+        match button {
+            SAppMouseButton::Invalid => 0,
+            _ => self.mouse_buttons[button as usize],
+        }
+    }
+
+    fn mouse_button_flags_mut(&mut self, button: SAppMouseButton) -> Option<&mut u8> {
+        match button {
+            SAppMouseButton::Invalid => None,
+            _ => Some(&mut self.mouse_buttons[button as usize]),
+        }
+    }
+
+    fn mark_changed_touches(&mut self, event: &SAppEvent) {
+        for active in self.touches.iter_mut() {
+            active.changed = changed_touches(event).any(|t| t.identifier == active.id);
+        }
+    }
+
+    fn release_all(&mut self) {
+        for flags in self.keys.iter_mut().chain(self.mouse_buttons.iter_mut()) {
+            if *flags & DOWN != 0 {
+                release(flags);
+            }
+        }
+        self.modifiers = SAppModifier::empty();
+        self.touches.clear();
+    }
+}
+
+fn release(flags: &mut u8) {
+    *flags = (*flags & !DOWN) | RELEASED;
+}
+
+fn changed_touches(event: &SAppEvent) -> impl Iterator<Item=&SAppTouchPoint> {
+    let num_touches = (event.num_touches.max(0) as usize).min(event.touches.len());
+    event.touches[..num_touches].iter().filter(|t| t.changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct InputApp {
+        input: InputState,
+    }
+
+    impl SApp for InputApp {
+        fn sapp_init(&mut self) {}
+
+        fn sapp_frame(&mut self) {}
+
+        fn sapp_cleanup(&mut self) {}
+
+        fn sapp_event(&mut self, event: SAppEvent) {
+            self.input.handle_event(&event);
+        }
+    }
+
+    fn headless() -> Headless<InputApp> {
+        Headless::new(InputApp::default(), &SAppDesc::default())
+    }
+
+    fn key(event_type: SAppEventType, key_code: SAppKeycode, key_repeat: bool) -> SAppEvent {
+        SAppEvent {
+            key_code,
+            key_repeat,
+            ..SAppEvent::new(event_type)
+        }
+    }
+
+    fn mouse(event_type: SAppEventType, mouse_button: SAppMouseButton) -> SAppEvent {
+        SAppEvent {
+            mouse_button,
+            mouse_x: 10.0,
+            mouse_y: 20.0,
+            ..SAppEvent::new(event_type)
+        }
+    }
+
+    fn touches(event_type: SAppEventType, points: &[(usize, f32, f32)]) -> SAppEvent {
+        let mut event = SAppEvent::new(event_type);
+        event.num_touches = points.len() as i32;
+        for (touch, &(identifier, x, y)) in event.touches.iter_mut().zip(points.iter()) {
+            touch.identifier = identifier;
+            touch.pos_x = x;
+            touch.pos_y = y;
+            touch.changed = true;
+        }
+        event
+    }
+
+    #[test]
+    fn key_edges() {
+        let mut headless = headless();
+        headless.event(key(SAppEventType::KeyDown, SAppKeycode::KeyA, false));
+
+        let input = &headless.app().input;
+        assert!(input.key_down(SAppKeycode::KeyA));
+        assert!(input.key_pressed(SAppKeycode::KeyA));
+        assert!(!input.key_released(SAppKeycode::KeyA));
+        assert!(!input.key_down(SAppKeycode::KeyW));
+
+        // the edges reset with the next frame, while the key is still held
+        headless.frame();
+        let input = &headless.app().input;
+        assert!(input.key_down(SAppKeycode::KeyA));
+        assert!(!input.key_pressed(SAppKeycode::KeyA));
+
+        headless.event(key(SAppEventType::KeyUp, SAppKeycode::KeyA, false));
+        let input = &headless.app().input;
+        assert!(!input.key_down(SAppKeycode::KeyA));
+        assert!(input.key_released(SAppKeycode::KeyA));
+
+        headless.frame();
+        assert!(!headless.app().input.key_released(SAppKeycode::KeyA));
+    }
+
+    #[test]
+    fn key_repeat() {
+        let mut headless = headless();
+        headless.event(key(SAppEventType::KeyDown, SAppKeycode::KeyA, false));
+        assert!(!headless.app().input.key_repeated(SAppKeycode::KeyA));
+
+        headless.frame();
+        headless.event(key(SAppEventType::KeyDown, SAppKeycode::KeyA, true));
+        let input = &headless.app().input;
+        assert!(input.key_repeated(SAppKeycode::KeyA));
+        assert!(!input.key_pressed(SAppKeycode::KeyA));
+        assert!(input.key_down(SAppKeycode::KeyA));
+
+        headless.frame();
+        assert!(!headless.app().input.key_repeated(SAppKeycode::KeyA));
+    }
+
+    #[test]
+    fn mouse_button_edges() {
+        let mut headless = headless();
+        headless.event(mouse(SAppEventType::MouseDown, SAppMouseButton::Left));
+
+        let input = &headless.app().input;
+        assert!(input.mouse_button_down(SAppMouseButton::Left));
+        assert!(input.mouse_button_pressed(SAppMouseButton::Left));
+        assert!(!input.mouse_button_down(SAppMouseButton::Right));
+        assert!(!input.mouse_button_down(SAppMouseButton::Invalid));
+        assert_eq!(input.mouse_pos(), Some((10.0, 20.0)));
+
+        headless.frame();
+        let input = &headless.app().input;
+        assert!(input.mouse_button_down(SAppMouseButton::Left));
+        assert!(!input.mouse_button_pressed(SAppMouseButton::Left));
+
+        headless.event(mouse(SAppEventType::MouseUp, SAppMouseButton::Left));
+        let input = &headless.app().input;
+        assert!(!input.mouse_button_down(SAppMouseButton::Left));
+        assert!(input.mouse_button_released(SAppMouseButton::Left));
+
+        headless.frame();
+        assert!(!headless.app().input.mouse_button_released(SAppMouseButton::Left));
+    }
+
+    #[test]
+    fn scroll_and_mouse_delta() {
+        let mut headless = headless();
+        let scroll = |x, y| SAppEvent {
+            scroll_x: x,
+            scroll_y: y,
+            ..SAppEvent::new(SAppEventType::MouseScroll)
+        };
+        let mouse_move = |x, y| SAppEvent {
+            mouse_x: x,
+            mouse_y: y,
+            ..SAppEvent::new(SAppEventType::MouseMove)
+        };
+
+        headless.event(scroll(1.0, 0.5));
+        headless.event(scroll(2.0, -1.5));
+        headless.event(mouse_move(10.0, 10.0));
+        headless.event(mouse_move(13.0, 8.0));
+        headless.event(mouse_move(15.0, 9.0));
+
+        let input = &headless.app().input;
+        assert_eq!(input.scroll(), (3.0, -1.0));
+        assert_eq!(input.mouse_delta(), (5.0, -1.0));
+        assert_eq!(input.mouse_pos(), Some((15.0, 9.0)));
+
+        headless.frame();
+        let input = &headless.app().input;
+        assert_eq!(input.scroll(), (0.0, 0.0));
+        assert_eq!(input.mouse_delta(), (0.0, 0.0));
+
+        headless.event(SAppEvent::new(SAppEventType::MouseLeave));
+        assert_eq!(headless.app().input.mouse_pos(), None);
+    }
+
+    #[test]
+    fn touches_added_and_removed() {
+        let mut headless = headless();
+        headless.event(touches(SAppEventType::TouchesBegan, &[(1, 10.0, 20.0)]));
+        headless.event(touches(SAppEventType::TouchesBegan, &[(2, 30.0, 40.0)]));
+
+        let active = headless.app().input.touches();
+        assert_eq!(active.len(), 2);
+        assert!(!active[0].changed);
+        assert!(active[1].changed);
+
+        headless.event(touches(SAppEventType::TouchesMoved, &[(1, 15.0, 25.0)]));
+        let active = headless.app().input.touches();
+        assert_eq!((active[0].id, active[0].x, active[0].y, active[0].changed), (1, 15.0, 25.0, true));
+        assert!(!active[1].changed);
+
+        headless.event(touches(SAppEventType::TouchesEnded, &[(1, 15.0, 25.0)]));
+        let active = headless.app().input.touches();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, 2);
+
+        headless.event(touches(SAppEventType::TouchesCancelled, &[(2, 30.0, 40.0)]));
+        assert!(headless.app().input.touches().is_empty());
+    }
+
+    #[test]
+    fn release_all_when_inactive() {
+        for event_type in [SAppEventType::Iconified, SAppEventType::Suspended] {
+            let mut headless = headless();
+            headless.event(SAppEvent {
+                modifiers: SAppModifier::SHIFT,
+                ..key(SAppEventType::KeyDown, SAppKeycode::KeyLeftShift, false)
+            });
+            headless.event(mouse(SAppEventType::MouseDown, SAppMouseButton::Right));
+            headless.event(touches(SAppEventType::TouchesBegan, &[(1, 10.0, 20.0)]));
+            headless.frame();
+
+            headless.event(SAppEvent::new(event_type));
+            let input = &headless.app().input;
+            assert!(!input.key_down(SAppKeycode::KeyLeftShift));
+            assert!(input.key_released(SAppKeycode::KeyLeftShift));
+            assert!(!input.mouse_button_down(SAppMouseButton::Right));
+            assert!(input.mouse_button_released(SAppMouseButton::Right));
+            assert_eq!(input.modifiers(), SAppModifier::empty());
+            assert!(input.touches().is_empty());
+        }
+    }
+}
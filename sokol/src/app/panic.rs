@@ -0,0 +1,59 @@
+//! Panics in application callbacks.
+//!
+//! Unwinding through the C frames of sokol_app is undefined behaviour. All
+//! callbacks into Rust code therefore catch panics, request sokol_app to quit,
+//! and skip any further callbacks but cleanup. `sapp_run()` then returns the
+//! panic as `SAppPanic`.
+
+use std::any::Any;
+use std::fmt;
+
+/// A panic caught in an application callback.
+pub struct SAppPanic {
+    payload: Box<dyn Any + Send + 'static>,
+}
+
+impl SAppPanic {
+    pub(crate) fn new(payload: Box<dyn Any + Send + 'static>) -> SAppPanic {
+        SAppPanic {
+            payload,
+        }
+    }
+
+    /// Returns the panic message, if the panic payload is a string.
+    pub fn message(&self) -> Option<&str> {
+        if let Some(s) = self.payload.downcast_ref::<&'static str>() {
+            Some(s)
+        } else if let Some(s) = self.payload.downcast_ref::<String>() {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// Continues unwinding with the original panic payload.
+    pub fn resume(self) -> ! {
+        std::panic::resume_unwind(self.payload)
+    }
+
+    pub fn into_payload(self) -> Box<dyn Any + Send + 'static> {
+        self.payload
+    }
+}
+
+impl fmt::Debug for SAppPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SAppPanic")
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl fmt::Display for SAppPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.message() {
+            Some(msg) => write!(f, "application panicked: {}", msg),
+            None => write!(f, "application panicked"),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Native platform handles.
+//!
+//! `sapp_platform()` returns the raw window and graphics handles of the
+//! target platform, e.g. to integrate third-party renderers or tools into a
+//! sokol window. There are no handles until sokol_app has created the window.
+//!
+//! With the `raw-window-handle` feature, `SAppPlatform` implements the
+//! `HasWindowHandle` and `HasDisplayHandle` traits of raw-window-handle 0.6:
+//!
+//! ```ignore
+//! let platform = sapp_platform().expect("no native window");
+//! let window = platform.window_handle()?;
+//! ```
+
+use std::os::raw::c_int;
+use std::os::raw::c_ulong;
+use std::os::raw::c_void;
+
+use super::*;
+
+/// Raw handles of the native window, and of the graphics device created
+/// by sokol_app.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SAppPlatform {
+    MacOS {
+        /// `NSWindow*`
+        ns_window: *const c_void,
+        /// `NSView*`, the content view of the window.
+        ns_view: *const c_void,
+        /// `MTLDevice`
+        mtl_device: *const c_void,
+    },
+    IOS {
+        /// `UIWindow*`
+        ui_window: *const c_void,
+        /// `UIView*`, the view of the root view controller.
+        ui_view: *const c_void,
+        /// `UIViewController*`
+        ui_view_controller: *const c_void,
+        /// `MTLDevice`
+        mtl_device: *const c_void,
+    },
+    Windows {
+        /// `HWND`
+        hwnd: *const c_void,
+        /// `HINSTANCE` of the executable.
+        hinstance: *const c_void,
+        /// `ID3D11Device*`, or null with OpenGL.
+        d3d11_device: *const c_void,
+        /// `ID3D11DeviceContext*`, or null with OpenGL.
+        d3d11_device_context: *const c_void,
+    },
+    X11 {
+        /// `Display*`
+        display: *mut c_void,
+        /// The default screen of the display.
+        screen: c_int,
+        /// `Window`
+        window: c_ulong,
+        /// `GLXContext`
+        glx_context: *const c_void,
+    },
+}
+
+impl SAppPlatform {
+    fn has_window(&self) -> bool {
+        match *self {
+            SAppPlatform::MacOS { ns_window, ns_view, .. } => !ns_window.is_null() && !ns_view.is_null(),
+            SAppPlatform::IOS { ui_window, ui_view, .. } => !ui_window.is_null() && !ui_view.is_null(),
+            SAppPlatform::Windows { hwnd, .. } => !hwnd.is_null(),
+            SAppPlatform::X11 { display, window, .. } => !display.is_null() && window != 0,
+        }
+    }
+}
+
+/// Returns the native handles of the target platform.
+///
+/// Returns `None` before sokol_app has created the window, with the dummy
+/// backend, and on platforms without native window handles, e.g. the browser.
+pub fn sapp_platform() -> Option<SAppPlatform> {
+    let platform = if cfg!(target_os = "macos") {
+        SAppPlatform::MacOS {
+            ns_window: sapp_macos_get_window(),
+            ns_view: unsafe { ffi::sokol_rs_macos_get_view() },
+            mtl_device: sapp_metal_get_device(),
+        }
+    } else if cfg!(target_os = "ios") {
+        SAppPlatform::IOS {
+            ui_window: sapp_ios_get_window(),
+            ui_view: unsafe { ffi::sokol_rs_ios_get_view() },
+            ui_view_controller: unsafe { ffi::sokol_rs_ios_get_view_controller() },
+            mtl_device: sapp_metal_get_device(),
+        }
+    } else if cfg!(target_os = "windows") {
+        SAppPlatform::Windows {
+            hwnd: sapp_win32_get_hwnd(),
+            hinstance: unsafe { ffi::sokol_rs_win32_get_hinstance() },
+            d3d11_device: sapp_d3d11_get_device(),
+            d3d11_device_context: sapp_d3d11_get_device_context(),
+        }
+    } else if cfg!(all(unix, not(target_os = "android"), not(target_os = "emscripten"))) {
+        SAppPlatform::X11 {
+            display: sapp_x11_get_display(),
+            screen: sapp_x11_get_screen(),
+            window: sapp_x11_get_window(),
+            glx_context: sapp_glx_get_context(),
+        }
+    } else {
+        return None;
+    };
+
+    if platform.has_window() {
+        Some(platform)
+    } else {
+        None
+    }
+}
+
+/// The handles are only valid until sokol_app has shut down. Null handles,
+/// e.g. of a `SAppPlatform` created by hand, are reported as
+/// `HandleError::Unavailable`.
+#[cfg(feature = "raw-window-handle")]
+impl raw_window_handle::HasWindowHandle for SAppPlatform {
+    fn window_handle(&self) -> Result<raw_window_handle::WindowHandle<'_>, raw_window_handle::HandleError> {
+        use std::num::NonZeroIsize;
+        use std::ptr::NonNull;
+
+        use raw_window_handle::*;
+
+        let raw = match *self {
+            SAppPlatform::MacOS { ns_view, .. } => {
+                let ns_view = NonNull::new(ns_view as *mut c_void).ok_or(HandleError::Unavailable)?;
+                RawWindowHandle::AppKit(AppKitWindowHandle::new(ns_view))
+            }
+            SAppPlatform::IOS { ui_view, ui_view_controller, .. } => {
+                let ui_view = NonNull::new(ui_view as *mut c_void).ok_or(HandleError::Unavailable)?;
+                let mut handle = UiKitWindowHandle::new(ui_view);
+                handle.ui_view_controller = NonNull::new(ui_view_controller as *mut c_void);
+                RawWindowHandle::UiKit(handle)
+            }
+            SAppPlatform::Windows { hwnd, hinstance, .. } => {
+                let hwnd = NonZeroIsize::new(hwnd as isize).ok_or(HandleError::Unavailable)?;
+                let mut handle = Win32WindowHandle::new(hwnd);
+                handle.hinstance = NonZeroIsize::new(hinstance as isize);
+                RawWindowHandle::Win32(handle)
+            }
+            SAppPlatform::X11 { window, .. } => {
+                if window == 0 {
+                    return Err(HandleError::Unavailable);
+                }
+                RawWindowHandle::Xlib(XlibWindowHandle::new(window))
+            }
+        };
+
+        unsafe {
+            Ok(WindowHandle::borrow_raw(raw))
+        }
+    }
+}
+
+#[cfg(feature = "raw-window-handle")]
+impl raw_window_handle::HasDisplayHandle for SAppPlatform {
+    fn display_handle(&self) -> Result<raw_window_handle::DisplayHandle<'_>, raw_window_handle::HandleError> {
+        use std::ptr::NonNull;
+
+        use raw_window_handle::*;
+
+        let raw = match *self {
+            SAppPlatform::MacOS { .. } => RawDisplayHandle::AppKit(AppKitDisplayHandle::new()),
+            SAppPlatform::IOS { .. } => RawDisplayHandle::UiKit(UiKitDisplayHandle::new()),
+            SAppPlatform::Windows { .. } => RawDisplayHandle::Windows(WindowsDisplayHandle::new()),
+            SAppPlatform::X11 { display, screen, .. } => {
+                let display = NonNull::new(display).ok_or(HandleError::Unavailable)?;
+                RawDisplayHandle::Xlib(XlibDisplayHandle::new(Some(display), screen))
+            }
+        };
+
+        unsafe {
+            Ok(DisplayHandle::borrow_raw(raw))
+        }
+    }
+}
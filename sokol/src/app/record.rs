@@ -0,0 +1,441 @@
+//! Event recording and replay.
+//!
+//! `EventRecorder` writes all application events, together with the frame
+//! count and the frame duration, to a compact binary stream. `EventReplayer`
+//! reads them back and feeds them into an `SApp` implementation, frame by
+//! frame, without a window:
+//!
+//! ```ignore
+//! // recording, in the application
+//! fn sapp_event(&mut self, event: SAppEvent) {
+//!     self.recorder.event(&event).unwrap();
+//!     self.input.handle_event(&event);
+//! }
+//!
+//! fn sapp_frame(&mut self) {
+//!     let dt = self.timer.laptime();
+//!     self.recorder.frame(dt).unwrap();
+//!     self.update(stm_sec(dt));
+//! }
+//!
+//! // replaying, e.g. in a test
+//! let mut replayer = EventReplayer::new(File::open("bug.rec")?)?;
+//! while replayer.replay_frame(&mut app)? {}
+//! ```
+//!
+//! While a replay is active, `sapp_frame_count()` and `FrameTimer` report
+//! the recorded values, so that per-frame input state and time steps match
+//! the recording.
+//!
+//! The stream starts with an 8 byte magic and a `u32` format version. It is
+//! followed by `F` (frame) and `E` (event) records. All values are stored
+//! in little endian byte order.
+
+use std::cell::Cell;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use super::*;
+use crate::time::stm_laptime;
+
+const MAGIC: &[u8; 8] = b"SAPPREC\0";
+const VERSION: u32 = 2;
+
+const FRAME_RECORD: u8 = b'F';
+const EVENT_RECORD: u8 = b'E';
+
+const EVENT_TYPES: [SAppEventType; 21] = [
+    SAppEventType::Invalid,
+    SAppEventType::KeyDown,
+    SAppEventType::KeyUp,
+    SAppEventType::Char,
+    SAppEventType::MouseDown,
+    SAppEventType::MouseUp,
+    SAppEventType::MouseScroll,
+    SAppEventType::MouseMove,
+    SAppEventType::MouseEnter,
+    SAppEventType::MouseLeave,
+    SAppEventType::TouchesBegan,
+    SAppEventType::TouchesMoved,
+    SAppEventType::TouchesEnded,
+    SAppEventType::TouchesCancelled,
+    SAppEventType::Resized,
+    SAppEventType::Iconified,
+    SAppEventType::Restored,
+    SAppEventType::Suspended,
+    SAppEventType::Resumed,
+    SAppEventType::UpdateCursor,
+    SAppEventType::QuitRequested,
+];
+
+#[derive(Copy, Clone)]
+struct ReplayState {
+    frame_count: u64,
+    frame_duration: u64,
+}
+
+thread_local! {
+    static REPLAY_STATE: Cell<Option<ReplayState>> = const { Cell::new(None) };
+}
+
+pub(super) fn replay_frame_count() -> Option<u64> {
+    REPLAY_STATE.with(|state| state.get().map(|s| s.frame_count))
+}
+
+/// Measures frame durations in ticks, see `sokol::time`.
+///
+/// While an `EventReplayer` is active, the recorded durations are returned.
+#[derive(Clone, Default, Debug)]
+pub struct FrameTimer {
+    last_time: u64,
+}
+
+impl FrameTimer {
+    pub fn new() -> FrameTimer {
+        Default::default()
+    }
+
+    /// Returns the time elapsed since the last call, like `stm_laptime()`.
+    pub fn laptime(&mut self) -> u64 {
+        match REPLAY_STATE.with(|state| state.get()) {
+            Some(state) => state.frame_duration,
+            None => stm_laptime(&mut self.last_time),
+        }
+    }
+}
+
+/// Writes application events and frame durations to a stream.
+pub struct EventRecorder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> EventRecorder<W> {
+    /// Creates a recorder, and writes the stream header.
+    pub fn new(mut writer: W) -> io::Result<EventRecorder<W>> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        Ok(EventRecorder {
+            writer,
+        })
+    }
+
+    /// Records an event. Call this for each event passed to `SApp::sapp_event()`.
+    pub fn event(&mut self, event: &SAppEvent) -> io::Result<()> {
+        let num_touches = (event.num_touches.max(0) as usize).min(event.touches.len());
+
+        let w = &mut self.writer;
+        w.write_all(&[EVENT_RECORD])?;
+        w.write_all(&event.frame_count.to_le_bytes())?;
+        w.write_all(&[event.event_type as u8])?;
+        w.write_all(&(event.key_code as u16).to_le_bytes())?;
+        w.write_all(&event.char_code.to_le_bytes())?;
+        w.write_all(&[event.key_repeat as u8, event.modifiers.bits() as u8, event.mouse_button as i8 as u8])?;
+        for v in [event.mouse_x, event.mouse_y, event.mouse_dx, event.mouse_dy, event.scroll_x, event.scroll_y].iter() {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        w.write_all(&[num_touches as u8])?;
+        for touch in event.touches[..num_touches].iter() {
+            w.write_all(&(touch.identifier as u64).to_le_bytes())?;
+            w.write_all(&touch.pos_x.to_le_bytes())?;
+            w.write_all(&touch.pos_y.to_le_bytes())?;
+            w.write_all(&[touch.changed as u8])?;
+        }
+        for v in [event.window_width, event.window_height,
+            event.framebuffer_width, event.framebuffer_height].iter() {
+            w.write_all(&v.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Records the start of a frame. Call this from `SApp::sapp_frame()`,
+    /// with the frame duration returned by `FrameTimer::laptime()`.
+    pub fn frame(&mut self, frame_duration: u64) -> io::Result<()> {
+        let w = &mut self.writer;
+        w.write_all(&[FRAME_RECORD])?;
+        w.write_all(&sapp_frame_count().to_le_bytes())?;
+        w.write_all(&frame_duration.to_le_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes the stream, and returns the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads application events recorded by an `EventRecorder`, and feeds them
+/// into an application.
+///
+/// Only events and frames are replayed. The application is responsible to
+/// call its own init and cleanup code.
+pub struct EventReplayer<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> EventReplayer<R> {
+    /// Creates a replayer, and checks the stream header.
+    pub fn new(mut reader: R) -> io::Result<EventReplayer<R>> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not an event recording"));
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != VERSION {
+            return Err(invalid_data("unsupported event recording version"));
+        }
+
+        Ok(EventReplayer {
+            reader,
+        })
+    }
+
+    /// Passes all events recorded before the next frame to
+    /// `SApp::sapp_event()`, then calls `SApp::sapp_frame()`.
+    ///
+    /// Returns false once the end of the recording is reached. Events
+    /// recorded after the last frame are still passed to the application.
+    pub fn replay_frame(&mut self, app: &mut dyn SApp) -> io::Result<bool> {
+        loop {
+            let mut tag = [0u8; 1];
+            if self.reader.read(&mut tag)? == 0 {
+                return Ok(false);
+            }
+
+            match tag[0] {
+                EVENT_RECORD => {
+                    let event = self.read_event()?;
+                    set_replay_state(event.frame_count, 0);
+                    app.sapp_event(event);
+                }
+                FRAME_RECORD => {
+                    let frame_count = read_u64(&mut self.reader)?;
+                    let frame_duration = read_u64(&mut self.reader)?;
+                    set_replay_state(frame_count, frame_duration);
+                    app.sapp_frame();
+                    return Ok(true);
+                }
+                _ => return Err(invalid_data("invalid record type")),
+            }
+        }
+    }
+
+    fn read_event(&mut self) -> io::Result<SAppEvent> {
+        let r = &mut self.reader;
+        let frame_count = read_u64(r)?;
+
+        let event_type = *EVENT_TYPES.get(read_u8(r)? as usize)
+            .ok_or_else(|| invalid_data("invalid event type"))?;
+
+        let mut event = SAppEvent::new(event_type);
+        event.frame_count = frame_count;
+
+        let key_code = read_u16(r)? as i32;
+        event.key_code = match key_code {
+            0 => SAppKeycode::KeyInvalid,
+            _ => SAppKeycode::from_code(key_code).ok_or_else(|| invalid_data("invalid key code"))?,
+        };
+
+        event.char_code = read_u32(r)?;
+        event.key_repeat = read_u8(r)? != 0;
+        event.modifiers = SAppModifier::from_bits_truncate(read_u8(r)? as u32);
+        event.mouse_button = match read_u8(r)? as i8 {
+            -1 => SAppMouseButton::Invalid,
+            0 => SAppMouseButton::Left,
+            1 => SAppMouseButton::Right,
+            2 => SAppMouseButton::Middle,
+            _ => return Err(invalid_data("invalid mouse button")),
+        };
+        event.mouse_x = read_f32(r)?;
+        event.mouse_y = read_f32(r)?;
+        event.mouse_dx = read_f32(r)?;
+        event.mouse_dy = read_f32(r)?;
+        event.scroll_x = read_f32(r)?;
+        event.scroll_y = read_f32(r)?;
+
+        let num_touches = read_u8(r)? as usize;
+        if num_touches > event.touches.len() {
+            return Err(invalid_data("too many touch points"));
+        }
+        event.num_touches = num_touches as i32;
+        for touch in event.touches[..num_touches].iter_mut() {
+            touch.identifier = read_u64(r)? as usize;
+            touch.pos_x = read_f32(r)?;
+            touch.pos_y = read_f32(r)?;
+            touch.changed = read_u8(r)? != 0;
+        }
+
+        event.window_width = read_i32(r)?;
+        event.window_height = read_i32(r)?;
+        event.framebuffer_width = read_i32(r)?;
+        event.framebuffer_height = read_i32(r)?;
+
+        Ok(event)
+    }
+}
+
+impl<R: Read> Drop for EventReplayer<R> {
+    fn drop(&mut self) {
+        REPLAY_STATE.with(|state| state.set(None));
+    }
+}
+
+fn set_replay_state(frame_count: u64, frame_duration: u64) {
+    REPLAY_STATE.with(|state| state.set(Some(ReplayState {
+        frame_count,
+        frame_duration,
+    })));
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+macro_rules! read_le {
+    ($name:ident, $ty:ty) => {
+        fn $name<R: Read>(r: &mut R) -> io::Result<$ty> {
+            let mut bytes = [0u8; std::mem::size_of::<$ty>()];
+            r.read_exact(&mut bytes)?;
+            Ok(<$ty>::from_le_bytes(bytes))
+        }
+    }
+}
+
+read_le!(read_u8, u8);
+read_le!(read_u16, u16);
+read_le!(read_u32, u32);
+read_le!(read_u64, u64);
+read_le!(read_i32, i32);
+read_le!(read_f32, f32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME_DURATIONS: [u64; 2] = [16_000_000, 17_000_000];
+
+    struct Recording {
+        recorder: Option<EventRecorder<Vec<u8>>>,
+        frames: usize,
+    }
+
+    impl SApp for Recording {
+        fn sapp_init(&mut self) {}
+
+        fn sapp_frame(&mut self) {
+            let recorder = self.recorder.as_mut().unwrap();
+            recorder.frame(FRAME_DURATIONS[self.frames]).unwrap();
+            self.frames += 1;
+        }
+
+        fn sapp_cleanup(&mut self) {}
+
+        fn sapp_event(&mut self, event: SAppEvent) {
+            self.recorder.as_mut().unwrap().event(&event).unwrap();
+        }
+    }
+
+    #[derive(Default)]
+    struct Replay {
+        events: Vec<(u64, Option<Event>)>,
+        frames: Vec<(u64, u64)>,
+        timer: FrameTimer,
+    }
+
+    impl SApp for Replay {
+        fn sapp_init(&mut self) {}
+
+        fn sapp_frame(&mut self) {
+            self.frames.push((sapp_frame_count(), self.timer.laptime()));
+        }
+
+        fn sapp_cleanup(&mut self) {}
+
+        fn sapp_event(&mut self, event: SAppEvent) {
+            assert_eq!(event.frame_count, sapp_frame_count());
+            self.events.push((event.frame_count, event.to_event()));
+        }
+    }
+
+    fn events() -> Vec<SAppEvent> {
+        let mut mouse_move = SAppEvent::new(SAppEventType::MouseMove);
+        mouse_move.mouse_x = 100.0;
+        mouse_move.mouse_y = 50.0;
+        mouse_move.mouse_dx = 3.5;
+        mouse_move.mouse_dy = -2.0;
+
+        let mut key_down = SAppEvent::new(SAppEventType::KeyDown);
+        key_down.key_code = SAppKeycode::KeyW;
+        key_down.modifiers = SAppModifier::SHIFT;
+
+        let mut touch = SAppEvent::new(SAppEventType::TouchesBegan);
+        touch.num_touches = 1;
+        touch.touches[0].identifier = 7;
+        touch.touches[0].pos_x = 10.0;
+        touch.touches[0].pos_y = 20.0;
+        touch.touches[0].changed = true;
+
+        vec![mouse_move, key_down, touch]
+    }
+
+    fn record() -> Vec<u8> {
+        let recording = Recording {
+            recorder: Some(EventRecorder::new(Vec::new()).unwrap()),
+            frames: 0,
+        };
+
+        let mut headless = Headless::new(recording, &SAppDesc::default());
+        let mut events = events().into_iter();
+        headless.event(events.next().unwrap());
+        headless.frame();
+        for event in events {
+            headless.event(event);
+        }
+        headless.frame();
+
+        let mut recording = headless.cleanup();
+        recording.recorder.take().unwrap().into_inner().unwrap()
+    }
+
+    #[test]
+    fn record_and_replay() {
+        let data = record();
+
+        let mut replay = Replay::default();
+        {
+            let mut replayer = EventReplayer::new(&data[..]).unwrap();
+            assert!(replayer.replay_frame(&mut replay).unwrap());
+            assert!(replayer.replay_frame(&mut replay).unwrap());
+            assert!(!replayer.replay_frame(&mut replay).unwrap());
+        }
+
+        let frame_counts = [0, 1, 1];
+        let expected = events().into_iter()
+            .zip(frame_counts.iter())
+            .map(|(event, frame_count)| (*frame_count, event.to_event()))
+            .collect::<Vec<_>>();
+        assert_eq!(replay.events, expected);
+        assert_eq!(replay.events[0].1, Some(Event::MouseMove { x: 100.0, y: 50.0, dx: 3.5, dy: -2.0 }));
+        assert_eq!(replay.frames, vec![(0, FRAME_DURATIONS[0]), (1, FRAME_DURATIONS[1])]);
+
+        // the replay state is reset with the replayer
+        assert_eq!(replay_frame_count(), None);
+    }
+
+    #[test]
+    fn invalid_header() {
+        let err = EventReplayer::new(&b"SAPPREC\0\x01\0\0\0"[..]).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = EventReplayer::new(&b"not a recording"[..]).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,390 @@
+//! A stack of application states.
+//!
+//! `StateStack` implements `SApp`, and forwards the callbacks to a stack of
+//! `State` objects, e.g. menus, gameplay and overlays. States switch to
+//! other states by returning a `Transition`, which is applied relative to
+//! the state returning it.
+//!
+//! ```ignore
+//! struct Menu;
+//!
+//! impl State<Assets> for Menu {
+//!     fn event(&mut self, _assets: &mut Assets, event: &SAppEvent) -> EventResult<Assets> {
+//!         if event.event_type == SAppEventType::KeyDown && event.key_code == SAppKeycode::KeyEnter {
+//!             return EventResult::Transition(Transition::Replace(Box::new(Game::new())));
+//!         }
+//!         EventResult::Ignored
+//!     }
+//! }
+//!
+//! let stack = StateStack::new(|| Assets::load(), Menu)
+//!     .event_filter(|_, event| simgui_handle_event(event))
+//!     .cleanup(|_| sg_shutdown());
+//!
+//! sapp_run(stack, SAppDesc::default());
+//! ```
+
+use super::*;
+
+/// A change of the state stack, returned by a `State`.
+///
+/// The "current" state is the one returning the transition. This isn't
+/// necessarily the top state, e.g. if an overlay passes an event on to the
+/// state below. States above the current state are left in place.
+pub enum Transition<C = ()> {
+    None,
+    /// Pushes a state directly above the current state.
+    Push(Box<dyn State<C>>),
+    /// Removes the current state. Popping the last state requests to quit.
+    Pop,
+    /// Replaces the current state.
+    Replace(Box<dyn State<C>>),
+    /// Requests to quit the application.
+    Quit,
+}
+
+/// The result of `State::event()`.
+pub enum EventResult<C = ()> {
+    /// The event is passed on to the state below.
+    Ignored,
+    /// The event is not passed on.
+    Consumed,
+    /// The event is not passed on, and the state stack is changed.
+    Transition(Transition<C>),
+}
+
+impl<C> From<bool> for EventResult<C> {
+    /// Converts the "handled" result of an event handler, like the one
+    /// returned by `simgui_handle_event()`.
+    fn from(consumed: bool) -> EventResult<C> {
+        if consumed {
+            EventResult::Consumed
+        } else {
+            EventResult::Ignored
+        }
+    }
+}
+
+/// A state of the application. All functions receive the context shared by
+/// all states of a `StateStack`.
+pub trait State<C = ()> {
+    /// Called when the state is pushed onto the stack.
+    fn enter(&mut self, _ctx: &mut C) {}
+
+    /// Called when the state is removed from the stack.
+    fn exit(&mut self, _ctx: &mut C) {}
+
+    /// Called once per frame, for the top state only.
+    fn update(&mut self, _ctx: &mut C) -> Transition<C> {
+        Transition::None
+    }
+
+    /// Called once per frame after `update()`, bottom-up for all visible states.
+    fn render(&mut self, _ctx: &mut C) {}
+
+    /// Called for each event, top-down until a state doesn't ignore it.
+    fn event(&mut self, _ctx: &mut C, _event: &SAppEvent) -> EventResult<C> {
+        EventResult::Ignored
+    }
+
+    /// Returns true if the state covers the states below, which are then not
+    /// rendered. Overlays, like a pause menu, return false.
+    fn is_opaque(&self) -> bool {
+        true
+    }
+}
+
+type InitFn<C> = Box<dyn FnOnce() -> C>;
+type CleanupFn<C> = Box<dyn FnOnce(C)>;
+type EventFilterFn<C> = Box<dyn FnMut(&mut C, &SAppEvent) -> bool>;
+
+/// Runs a stack of `State` objects as an `SApp` implementation.
+pub struct StateStack<C = ()> {
+    init: Option<InitFn<C>>,
+    cleanup: Option<CleanupFn<C>>,
+    event_filter: Option<EventFilterFn<C>>,
+    ctx: Option<C>,
+    initial_state: Option<Box<dyn State<C>>>,
+    states: Vec<Box<dyn State<C>>>,
+}
+
+impl<C: 'static> StateStack<C> {
+    /// Creates the stack. Once sokol_app is initialized, `init` creates the
+    /// context shared by all states, and `initial_state` is entered.
+    pub fn new<F, S>(init: F, initial_state: S) -> StateStack<C>
+        where F: FnOnce() -> C + 'static,
+              S: State<C> + 'static {
+        StateStack {
+            init: Some(Box::new(init)),
+            cleanup: None,
+            event_filter: None,
+            ctx: None,
+            initial_state: Some(Box::new(initial_state)),
+            states: Vec::new(),
+        }
+    }
+
+    /// Called with the context after all states have been exited.
+    pub fn cleanup<F>(mut self, cleanup: F) -> StateStack<C>
+        where F: FnOnce(C) + 'static {
+        self.cleanup = Some(Box::new(cleanup));
+        self
+    }
+
+    /// Called for each event before any state. Returns true if the event
+    /// has been consumed, e.g. by `simgui_handle_event()`.
+    pub fn event_filter<F>(mut self, event_filter: F) -> StateStack<C>
+        where F: FnMut(&mut C, &SAppEvent) -> bool + 'static {
+        self.event_filter = Some(Box::new(event_filter));
+        self
+    }
+}
+
+impl<C> StateStack<C> {
+    /// The number of states on the stack.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Applies a transition returned by the state at `index`.
+    fn apply(&mut self, index: usize, transition: Transition<C>) {
+        let ctx = match self.ctx.as_mut() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+
+        match transition {
+            Transition::None => {}
+            Transition::Push(mut state) => {
+                state.enter(ctx);
+                self.states.insert(index + 1, state);
+            }
+            Transition::Pop => {
+                self.states.remove(index).exit(ctx);
+                if self.states.is_empty() {
+                    sapp_request_quit();
+                }
+            }
+            Transition::Replace(mut state) => {
+                self.states[index].exit(ctx);
+                state.enter(ctx);
+                self.states[index] = state;
+            }
+            Transition::Quit => {
+                sapp_request_quit();
+            }
+        }
+    }
+}
+
+impl<C> SApp for StateStack<C> {
+    fn sapp_init(&mut self) {
+        if let Some(init) = self.init.take() {
+            self.ctx = Some(init());
+        }
+        if let (Some(ctx), Some(mut state)) = (self.ctx.as_mut(), self.initial_state.take()) {
+            state.enter(ctx);
+            self.states.push(state);
+        }
+    }
+
+    fn sapp_frame(&mut self) {
+        let transition = match (self.ctx.as_mut(), self.states.last_mut()) {
+            (Some(ctx), Some(state)) => state.update(ctx),
+            _ => Transition::None,
+        };
+        if !self.states.is_empty() {
+            self.apply(self.states.len() - 1, transition);
+        }
+
+        if let Some(ctx) = self.ctx.as_mut() {
+            let first_visible = self.states.iter()
+                .rposition(|state| state.is_opaque())
+                .unwrap_or(0);
+
+            for state in self.states[first_visible..].iter_mut() {
+                state.render(ctx);
+            }
+        }
+    }
+
+    fn sapp_cleanup(&mut self) {
+        if let Some(ctx) = self.ctx.as_mut() {
+            while let Some(mut state) = self.states.pop() {
+                state.exit(ctx);
+            }
+        }
+        if let (Some(ctx), Some(cleanup)) = (self.ctx.take(), self.cleanup.take()) {
+            cleanup(ctx);
+        }
+    }
+
+    fn sapp_event(&mut self, event: SAppEvent) {
+        let ctx = match self.ctx.as_mut() {
+            Some(ctx) => ctx,
+            None => return,
+        };
+
+        if let Some(event_filter) = self.event_filter.as_mut() {
+            if event_filter(ctx, &event) {
+                return;
+            }
+        }
+
+        let mut transition = None;
+        for (index, state) in self.states.iter_mut().enumerate().rev() {
+            match state.event(ctx, &event) {
+                EventResult::Ignored => continue,
+                EventResult::Consumed => {}
+                EventResult::Transition(t) => transition = Some((index, t)),
+            }
+            break;
+        }
+        if let Some((index, transition)) = transition {
+            self.apply(index, transition);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Log = Vec<String>;
+
+    struct TestState {
+        name: &'static str,
+        opaque: bool,
+        on_update: Option<Transition<Log>>,
+        on_key: Option<Transition<Log>>,
+    }
+
+    impl TestState {
+        fn new(name: &'static str) -> TestState {
+            TestState {
+                name,
+                opaque: true,
+                on_update: None,
+                on_key: None,
+            }
+        }
+
+        fn overlay(name: &'static str) -> TestState {
+            TestState {
+                opaque: false,
+                ..TestState::new(name)
+            }
+        }
+    }
+
+    impl State<Log> for TestState {
+        fn enter(&mut self, log: &mut Log) {
+            log.push(format!("enter {}", self.name));
+        }
+
+        fn exit(&mut self, log: &mut Log) {
+            log.push(format!("exit {}", self.name));
+        }
+
+        fn update(&mut self, _log: &mut Log) -> Transition<Log> {
+            self.on_update.take().unwrap_or(Transition::None)
+        }
+
+        fn render(&mut self, log: &mut Log) {
+            log.push(format!("render {}", self.name));
+        }
+
+        fn event(&mut self, _log: &mut Log, event: &SAppEvent) -> EventResult<Log> {
+            match self.on_key.take() {
+                Some(transition) if event.event_type == SAppEventType::KeyDown => {
+                    EventResult::Transition(transition)
+                }
+                _ => EventResult::Ignored,
+            }
+        }
+
+        fn is_opaque(&self) -> bool {
+            self.opaque
+        }
+    }
+
+    fn key_down() -> SAppEvent {
+        SAppEvent::new(SAppEventType::KeyDown)
+    }
+
+    fn take_log(stack: &mut StateStack<Log>) -> Log {
+        std::mem::take(stack.ctx.as_mut().unwrap())
+    }
+
+    #[test]
+    fn transitions_apply_to_the_returning_state() {
+        let game = TestState {
+            on_update: Some(Transition::Push(Box::new(TestState::overlay("hud")))),
+            on_key: Some(Transition::Replace(Box::new(TestState::new("menu")))),
+            ..TestState::new("game")
+        };
+        let mut stack = StateStack::new(Vec::new, game);
+
+        stack.sapp_init();
+        stack.sapp_frame();
+        assert_eq!(take_log(&mut stack), ["enter game", "enter hud", "render game", "render hud"]);
+
+        // the overlay ignores the event, so the game below replaces itself
+        stack.sapp_event(key_down());
+        stack.sapp_frame();
+        assert_eq!(take_log(&mut stack), ["exit game", "enter menu", "render menu", "render hud"]);
+        assert_eq!(stack.len(), 2);
+
+        stack.sapp_cleanup();
+    }
+
+    #[test]
+    fn push_and_pop_below_overlay() {
+        let game = TestState {
+            on_update: Some(Transition::Push(Box::new(TestState::overlay("hud")))),
+            on_key: Some(Transition::Push(Box::new(TestState {
+                on_key: Some(Transition::Pop),
+                ..TestState::overlay("pause")
+            }))),
+            ..TestState::new("game")
+        };
+        let mut stack = StateStack::new(Vec::new, game);
+
+        stack.sapp_init();
+        stack.sapp_frame();
+        take_log(&mut stack);
+
+        stack.sapp_event(key_down());
+        stack.sapp_frame();
+        assert_eq!(take_log(&mut stack), ["enter pause", "render game", "render pause", "render hud"]);
+
+        stack.sapp_event(key_down());
+        stack.sapp_frame();
+        assert_eq!(take_log(&mut stack), ["exit pause", "render game", "render hud"]);
+
+        stack.sapp_cleanup();
+    }
+
+    #[test]
+    fn popping_the_last_state_quits() {
+        let game = TestState {
+            on_key: Some(Transition::Pop),
+            ..TestState::new("game")
+        };
+        let mut headless = Headless::new(StateStack::new(Vec::new, game), &SAppDesc::default());
+
+        headless.init();
+        headless.frame();
+        headless.event(key_down());
+        assert!(headless.app().is_empty());
+        assert!(!headless.quit_ordered());
+
+        headless.frame();
+        assert!(headless.quit_ordered());
+        headless.cleanup();
+    }
+}
@@ -0,0 +1,489 @@
+//! Single-line text editing.
+//!
+//! `TextInput` maintains a UTF-8 string with a cursor and a selection, and
+//! is edited with `Char` and `KeyDown` events. It doesn't render anything,
+//! so it can back custom UI widgets as well as imgui text fields.
+//!
+//! Supported keys:
+//!
+//! - Left/Right move by character, or by word with Ctrl or Alt held.
+//! - Home/End move to the start and end of the text.
+//! - Holding Shift while moving extends the selection.
+//! - Backspace/Delete remove the selection, a character, or a word with
+//!   Ctrl or Alt held.
+//! - Ctrl+A selects all, Ctrl+Z undoes, Ctrl+Y and Ctrl+Shift+Z redo. On
+//!   MacOS, the Super (command) key can be used instead of Ctrl.
+
+use std::ops::Range;
+
+use super::*;
+
+const MAX_UNDO_STEPS: usize = 100;
+
+#[derive(Clone, Debug)]
+struct Snapshot {
+    text: String,
+    cursor: usize,
+    anchor: usize,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum EditKind {
+    Typing,
+    Other,
+}
+
+/// A text buffer, with cursor, selection and undo history.
+///
+/// Cursor and selection are byte offsets into the text, and always at
+/// character boundaries.
+#[derive(Clone, Default, Debug)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    // the other end of the selection, equal to `cursor` if nothing is selected
+    anchor: usize,
+    max_chars: Option<usize>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    // consecutive typing is undone in one step
+    last_edit: Option<EditKind>,
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        Default::default()
+    }
+
+    /// Creates a text input with the given text, and the cursor at its end.
+    pub fn with_text(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        input.set_text(text);
+        input
+    }
+
+    /// Limits the length of the text, in characters.
+    pub fn with_max_chars(mut self, max_chars: usize) -> TextInput {
+        self.max_chars = Some(max_chars);
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, moves the cursor to its end, and clears the undo history.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = match self.max_chars {
+            Some(max_chars) => text.chars().take(max_chars).collect(),
+            None => text.to_string(),
+        };
+        self.cursor = self.text.len();
+        self.anchor = self.cursor;
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = None;
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The selected byte range, or `None` if nothing is selected.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        if self.cursor == self.anchor {
+            None
+        } else {
+            Some(self.cursor.min(self.anchor)..self.cursor.max(self.anchor))
+        }
+    }
+
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some(range) => &self.text[range],
+            None => "",
+        }
+    }
+
+    /// Updates the text from an application event. Returns true if the
+    /// event has been handled.
+    pub fn handle_event(&mut self, event: &SAppEvent) -> bool {
+        let command = event.modifiers.intersects(SAppModifier::CONTROL | SAppModifier::SUPER);
+        let word = event.modifiers.intersects(SAppModifier::CONTROL | SAppModifier::ALT);
+        let select = event.modifiers.contains(SAppModifier::SHIFT);
+        // AltGr is reported as Ctrl+Alt on some platforms, and produces characters
+        let shortcut = event.modifiers.contains(SAppModifier::SUPER)
+            || (event.modifiers.contains(SAppModifier::CONTROL) && !event.modifiers.contains(SAppModifier::ALT));
+
+        match event.event_type {
+            SAppEventType::Char => {
+                match std::char::from_u32(event.char_code) {
+                    Some(c) if !c.is_control() && !shortcut => {
+                        let mut buf = [0u8; 4];
+                        self.edit(EditKind::Typing, |input| input.insert_str(c.encode_utf8(&mut buf)));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            SAppEventType::KeyDown => {
+                match event.key_code {
+                    SAppKeycode::KeyLeft => self.move_left(word, select),
+                    SAppKeycode::KeyRight => self.move_right(word, select),
+                    SAppKeycode::KeyHome => self.move_to(0, select),
+                    SAppKeycode::KeyEnd => self.move_to(self.text.len(), select),
+                    SAppKeycode::KeyBackspace => self.backspace(word),
+                    SAppKeycode::KeyDelete => self.delete(word),
+                    SAppKeycode::KeyA if command => self.select_all(),
+                    SAppKeycode::KeyZ if command && select => { self.redo(); }
+                    SAppKeycode::KeyZ if command => { self.undo(); }
+                    SAppKeycode::KeyY if command => { self.redo(); }
+                    _ => return false,
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Replaces the selection with `s`, or inserts it at the cursor.
+    pub fn insert(&mut self, s: &str) {
+        self.edit(EditKind::Other, |input| input.insert_str(s));
+    }
+
+    /// Moves the cursor one character or word to the left.
+    pub fn move_left(&mut self, word: bool, select: bool) {
+        let pos = match self.selection() {
+            Some(range) if !select && !word => range.start,
+            _ if word => self.prev_word_boundary(self.cursor),
+            _ => self.prev_char_boundary(self.cursor),
+        };
+        self.move_to(pos, select);
+    }
+
+    /// Moves the cursor one character or word to the right.
+    pub fn move_right(&mut self, word: bool, select: bool) {
+        let pos = match self.selection() {
+            Some(range) if !select && !word => range.end,
+            _ if word => self.next_word_boundary(self.cursor),
+            _ => self.next_char_boundary(self.cursor),
+        };
+        self.move_to(pos, select);
+    }
+
+    /// Moves the cursor to a byte offset. The selection is extended if
+    /// `select` is set, and cleared otherwise.
+    ///
+    /// Panics if `pos` is not at a character boundary.
+    pub fn move_to(&mut self, pos: usize, select: bool) {
+        assert!(self.text.is_char_boundary(pos), "cursor position not at a character boundary");
+
+        self.cursor = pos;
+        if !select {
+            self.anchor = pos;
+        }
+        self.last_edit = None;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.cursor = self.text.len();
+        self.last_edit = None;
+    }
+
+    /// Removes the selection, or the character or word before the cursor.
+    pub fn backspace(&mut self, word: bool) {
+        if self.selection().is_none() {
+            self.anchor = if word {
+                self.prev_word_boundary(self.cursor)
+            } else {
+                self.prev_char_boundary(self.cursor)
+            };
+        }
+        self.edit(EditKind::Other, |input| input.insert_str(""));
+    }
+
+    /// Removes the selection, or the character or word after the cursor.
+    pub fn delete(&mut self, word: bool) {
+        if self.selection().is_none() {
+            self.anchor = if word {
+                self.next_word_boundary(self.cursor)
+            } else {
+                self.next_char_boundary(self.cursor)
+            };
+        }
+        self.edit(EditKind::Other, |input| input.insert_str(""));
+    }
+
+    /// Reverts the last edit. Returns false if there's nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(snapshot) => {
+                let current = self.snapshot();
+                self.redo.push(current);
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Repeats the last undone edit. Returns false if there's nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                let current = self.snapshot();
+                self.undo.push(current);
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn edit<F: FnOnce(&mut TextInput)>(&mut self, kind: EditKind, f: F) {
+        let before = self.snapshot();
+        f(self);
+
+        if self.text == before.text {
+            // restore the selection of no-op edits, e.g. backspace at the start
+            self.anchor = self.cursor;
+            return;
+        }
+
+        if kind != EditKind::Typing || self.last_edit != Some(EditKind::Typing) {
+            self.undo.push(before);
+            if self.undo.len() > MAX_UNDO_STEPS {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.last_edit = Some(kind);
+    }
+
+    fn insert_str(&mut self, s: &str) {
+        let range = self.cursor.min(self.anchor)..self.cursor.max(self.anchor);
+
+        let s = match self.max_chars {
+            Some(max_chars) => {
+                let remaining = self.text.chars().count() - self.text[range.clone()].chars().count();
+                let end = s.char_indices()
+                    .nth(max_chars.saturating_sub(remaining))
+                    .map_or(s.len(), |(i, _)| i);
+                &s[..end]
+            }
+            None => s,
+        };
+
+        self.text.replace_range(range.clone(), s);
+        self.cursor = range.start + s.len();
+        self.anchor = self.cursor;
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            text: self.text.clone(),
+            cursor: self.cursor,
+            anchor: self.anchor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.text = snapshot.text;
+        self.cursor = snapshot.cursor;
+        self.anchor = snapshot.anchor;
+        self.last_edit = None;
+    }
+
+    fn prev_char_boundary(&self, pos: usize) -> usize {
+        self.text[..pos].chars().next_back().map_or(pos, |c| pos - c.len_utf8())
+    }
+
+    fn next_char_boundary(&self, pos: usize) -> usize {
+        self.text[pos..].chars().next().map_or(pos, |c| pos + c.len_utf8())
+    }
+
+    /// Skips non-word characters, then word characters, to the left.
+    fn prev_word_boundary(&self, pos: usize) -> usize {
+        let mut chars = self.text[..pos].char_indices().rev().peekable();
+        let mut boundary = pos;
+
+        while let Some(&(i, c)) = chars.peek() {
+            if is_word_char(c) {
+                break;
+            }
+            boundary = i;
+            chars.next();
+        }
+        while let Some(&(i, c)) = chars.peek() {
+            if !is_word_char(c) {
+                break;
+            }
+            boundary = i;
+            chars.next();
+        }
+
+        boundary
+    }
+
+    /// Skips non-word characters, then word characters, to the right.
+    fn next_word_boundary(&self, pos: usize) -> usize {
+        let mut chars = self.text[pos..].char_indices().peekable();
+        let mut boundary = pos;
+
+        while let Some(&(i, c)) = chars.peek() {
+            if is_word_char(c) {
+                break;
+            }
+            boundary = pos + i + c.len_utf8();
+            chars.next();
+        }
+        while let Some(&(i, c)) = chars.peek() {
+            if !is_word_char(c) {
+                break;
+            }
+            boundary = pos + i + c.len_utf8();
+            chars.next();
+        }
+
+        boundary
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn char_event(c: char, modifiers: SAppModifier) -> SAppEvent {
+        let mut event = SAppEvent::new(SAppEventType::Char);
+        event.char_code = c as u32;
+        event.modifiers = modifiers;
+        event
+    }
+
+    fn key_event(key: SAppKeycode, modifiers: SAppModifier) -> SAppEvent {
+        let mut event = SAppEvent::new(SAppEventType::KeyDown);
+        event.key_code = key;
+        event.modifiers = modifiers;
+        event
+    }
+
+    fn type_str(input: &mut TextInput, s: &str) {
+        for c in s.chars() {
+            assert!(input.handle_event(&char_event(c, SAppModifier::empty())));
+        }
+    }
+
+    fn key(input: &mut TextInput, key: SAppKeycode, modifiers: SAppModifier) {
+        assert!(input.handle_event(&key_event(key, modifiers)));
+    }
+
+    #[test]
+    fn typing() {
+        let mut input = TextInput::new();
+        type_str(&mut input, "héllo");
+        assert_eq!(input.text(), "héllo");
+        assert_eq!(input.cursor(), "héllo".len());
+
+        // shortcuts and control characters are not inserted
+        assert!(!input.handle_event(&char_event('s', SAppModifier::CONTROL)));
+        assert!(!input.handle_event(&char_event('s', SAppModifier::SUPER)));
+        assert!(!input.handle_event(&char_event('\u{8}', SAppModifier::empty())));
+        assert_eq!(input.text(), "héllo");
+    }
+
+    #[test]
+    fn alt_gr_characters() {
+        let mut input = TextInput::new();
+        assert!(input.handle_event(&char_event('@', SAppModifier::CONTROL | SAppModifier::ALT)));
+        assert!(input.handle_event(&char_event('€', SAppModifier::ALT)));
+        assert_eq!(input.text(), "@€");
+    }
+
+    #[test]
+    fn movement_and_selection() {
+        let mut input = TextInput::with_text("one two_2 three");
+
+        key(&mut input, SAppKeycode::KeyLeft, SAppModifier::CONTROL);
+        assert_eq!(input.cursor(), 10);
+        key(&mut input, SAppKeycode::KeyLeft, SAppModifier::CONTROL | SAppModifier::SHIFT);
+        assert_eq!(input.selected_text(), "two_2 ");
+
+        // moving without Shift collapses the selection to its start
+        key(&mut input, SAppKeycode::KeyLeft, SAppModifier::empty());
+        assert_eq!((input.cursor(), input.selection()), (4, None));
+
+        key(&mut input, SAppKeycode::KeyEnd, SAppModifier::SHIFT);
+        assert_eq!(input.selected_text(), "two_2 three");
+        key(&mut input, SAppKeycode::KeyHome, SAppModifier::empty());
+        assert_eq!(input.cursor(), 0);
+        key(&mut input, SAppKeycode::KeyRight, SAppModifier::ALT);
+        assert_eq!(input.cursor(), 3);
+
+        key(&mut input, SAppKeycode::KeyA, SAppModifier::CONTROL);
+        assert_eq!(input.selected_text(), "one two_2 three");
+    }
+
+    #[test]
+    fn deletion() {
+        let mut input = TextInput::with_text("añb cd");
+        key(&mut input, SAppKeycode::KeyBackspace, SAppModifier::CONTROL);
+        assert_eq!(input.text(), "añb ");
+        key(&mut input, SAppKeycode::KeyLeft, SAppModifier::empty());
+        key(&mut input, SAppKeycode::KeyLeft, SAppModifier::empty());
+        key(&mut input, SAppKeycode::KeyBackspace, SAppModifier::empty());
+        assert_eq!(input.text(), "ab ");
+        key(&mut input, SAppKeycode::KeyDelete, SAppModifier::empty());
+        assert_eq!(input.text(), "a ");
+
+        key(&mut input, SAppKeycode::KeyHome, SAppModifier::empty());
+        key(&mut input, SAppKeycode::KeyBackspace, SAppModifier::empty());
+        assert_eq!((input.text(), input.cursor()), ("a ", 0));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut input = TextInput::new();
+        type_str(&mut input, "abc");
+        key(&mut input, SAppKeycode::KeyBackspace, SAppModifier::empty());
+        type_str(&mut input, "de");
+        assert_eq!(input.text(), "abde");
+
+        // consecutive typing is undone in one step
+        key(&mut input, SAppKeycode::KeyZ, SAppModifier::CONTROL);
+        assert_eq!(input.text(), "ab");
+        key(&mut input, SAppKeycode::KeyZ, SAppModifier::CONTROL);
+        assert_eq!(input.text(), "abc");
+        key(&mut input, SAppKeycode::KeyZ, SAppModifier::CONTROL);
+        assert_eq!(input.text(), "");
+        assert!(!input.undo());
+
+        key(&mut input, SAppKeycode::KeyY, SAppModifier::CONTROL);
+        assert_eq!(input.text(), "abc");
+        key(&mut input, SAppKeycode::KeyZ, SAppModifier::SUPER | SAppModifier::SHIFT);
+        assert_eq!(input.text(), "ab");
+
+        // a new edit clears the redo history
+        type_str(&mut input, "x");
+        assert!(!input.redo());
+        assert_eq!(input.text(), "abx");
+    }
+
+    #[test]
+    fn max_chars() {
+        let mut input = TextInput::with_text("abcdef").with_max_chars(4);
+        input.set_text("äbcdef");
+        assert_eq!(input.text(), "äbcd");
+
+        type_str(&mut input, "e");
+        assert_eq!(input.text(), "äbcd");
+
+        input.select_all();
+        input.insert("wxyz12");
+        assert_eq!(input.text(), "wxyz");
+    }
+}
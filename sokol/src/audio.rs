@@ -3,10 +3,22 @@
 //! A Rust API to the [sokol_audio.h](https://github.com/floooh/sokol/blob/master/sokol_audio.h)
 //! header-only C library.
 
+use std::fmt;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use crate::app::PanicPayload;
+
 pub mod ffi {
     use std::os::raw::c_int;
     use std::os::raw::c_void;
     use std::ptr::null;
+    use std::ptr::null_mut;
+    use std::slice::from_raw_parts_mut;
 
     #[repr(C)]
     #[derive(Debug)]
@@ -33,11 +45,7 @@ pub mod ffi {
         pub fn saudio_push(frames: *const f32, num_frames: c_int) -> c_int;
     }
 
-    pub fn saudio_make_desc(desc: super::SAudioDesc) -> SAudioDesc {
-        let app_ptr = unsafe {
-            super::super::app::ffi::sapp_userdata()
-        };
-
+    pub fn saudio_make_desc(desc: &super::SAudioDesc) -> SAudioDesc {
         SAudioDesc {
             sample_rate: desc.sample_rate,
             num_channels: desc.num_channels,
@@ -45,36 +53,131 @@ pub mod ffi {
             packet_frames: desc.packet_frames,
             num_packets: desc.num_packets,
             stream_cb: null(),
-            stream_userdata_cb: if desc.use_stream_cb {
-                Some(super::super::app::ffi::stream_userdata_cb)
+            stream_userdata_cb: if desc.stream_cb.is_some() {
+                Some(stream_userdata_cb)
             } else {
                 None
             },
-            user_data: app_ptr,
+            user_data: null_mut(),
         }
     }
+
+    unsafe extern fn stream_userdata_cb(buffer: *mut f32, num_frames: c_int, num_channels: c_int, _user_data: *mut c_void) {
+        let arr = from_raw_parts_mut(buffer, (num_frames * num_channels) as usize);
+        super::stream_cb(arr, num_frames, num_channels);
+    }
+}
+
+/// The audio stream callback.
+///
+/// The stream is owned by the `audio` module while `saudio_setup()` is in
+/// effect, and is called from a separate thread on all desktop platforms.
+pub trait SAudioStream: Send {
+    /// Called by `sokol_audio` in callback mode. Applications are expected to
+    /// write `num_frames * num_channels` interleaved samples into the buffer.
+    fn saudio_stream(&mut self, buffer: &mut [f32], num_frames: i32, num_channels: i32);
+}
+
+impl<F> SAudioStream for F
+    where F: FnMut(&mut [f32], i32, i32) + Send {
+    fn saudio_stream(&mut self, buffer: &mut [f32], num_frames: i32, num_channels: i32) {
+        self(buffer, num_frames, num_channels)
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub struct SAudioDesc {
     pub sample_rate: i32,
     pub num_channels: i32,
     pub buffer_frames: i32,
     pub packet_frames: i32,
     pub num_packets: i32,
-    pub use_stream_cb: bool,
+    /// Enables callback mode. Leave at `None` to push samples with `saudio_push()`.
+    pub stream_cb: Option<Box<dyn SAudioStream>>,
+}
+
+impl fmt::Debug for SAudioDesc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SAudioDesc")
+            .field("sample_rate", &self.sample_rate)
+            .field("num_channels", &self.num_channels)
+            .field("buffer_frames", &self.buffer_frames)
+            .field("packet_frames", &self.packet_frames)
+            .field("num_packets", &self.num_packets)
+            .field("stream_cb", &self.stream_cb.is_some())
+            .finish()
+    }
+}
+
+struct StreamState {
+    stream: Option<Box<dyn SAudioStream>>,
+    panic: Option<PanicPayload>,
+}
+
+// shared between the application and the audio thread
+static STREAM: Mutex<StreamState> = Mutex::new(StreamState {
+    stream: None,
+    panic: None,
+});
+
+// set with the panic, so that the frame callback doesn't need to lock STREAM
+static STREAM_PANICKED: AtomicBool = AtomicBool::new(false);
+
+fn lock_stream() -> MutexGuard<'static, StreamState> {
+    // panics are caught while the lock is held, so it can't be poisoned
+    STREAM.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn stream_cb(buffer: &mut [f32], num_frames: i32, num_channels: i32) {
+    let mut state = lock_stream();
+
+    if state.panic.is_none() {
+        if let Some(stream) = state.stream.as_mut() {
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                stream.saudio_stream(buffer, num_frames, num_channels)
+            }));
+
+            match result {
+                Ok(_) => return,
+                Err(payload) => {
+                    state.panic = Some(payload);
+                    STREAM_PANICKED.store(true, Ordering::Release);
+                }
+            }
+        }
+    }
+
+    for sample in buffer.iter_mut() {
+        *sample = 0.0;
+    }
+}
+
+/// Returns true if the audio stream callback panicked.
+pub(crate) fn saudio_stream_panicked() -> bool {
+    STREAM_PANICKED.load(Ordering::Acquire)
+}
+
+pub(crate) fn take_saudio_stream_panic() -> Option<PanicPayload> {
+    let payload = lock_stream().panic.take();
+    STREAM_PANICKED.store(false, Ordering::Release);
+    payload
 }
 
 pub fn saudio_setup(desc: SAudioDesc) {
+    let c_desc = ffi::saudio_make_desc(&desc);
+    lock_stream().stream = desc.stream_cb;
+
     unsafe {
-        ffi::saudio_setup(&ffi::saudio_make_desc(desc))
+        ffi::saudio_setup(&c_desc)
     }
 }
 
+/// Shuts down `sokol_audio`, and drops the audio stream.
 pub fn saudio_shutdown() {
     unsafe {
         ffi::saudio_shutdown();
     }
+    lock_stream().stream = None;
 }
 
 pub fn saudio_isvalid() -> bool {
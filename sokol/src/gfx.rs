@@ -5,9 +5,33 @@
 
 use std::fmt;
 use std::os::raw::c_void;
+use std::ptr::null;
+
+use crate::marshal::MarshalArena;
+
+pub use self::content::*;
+pub use self::error::*;
+pub use self::fixed::*;
+pub use self::resource::*;
+pub use self::trace::*;
+pub use self::uniform::*;
+pub use self::vertex::*;
+
+#[cfg(feature = "derive")]
+pub use sokol_derive::UniformBlock;
+#[cfg(feature = "derive")]
+pub use sokol_derive::Vertex;
+
+mod content;
+mod error;
+mod fixed;
+mod label;
+mod resource;
+mod trace;
+mod uniform;
+mod vertex;
 
 mod ffi {
-    use std::ffi::CString;
     use std::fmt;
     use std::os::raw::c_char;
     use std::os::raw::c_int;
@@ -15,26 +39,27 @@ mod ffi {
     use std::ptr::null;
 
     use crate::app::ffi::*;
+    use crate::marshal::MarshalArena;
 
-    const _SG_INVALID_ID: usize = 0;
+    pub const SG_INVALID_ID: i32 = 0;
     const _SG_NUM_SHADER_STAGES: usize = 2;
     const SG_NUM_INFLIGHT_FRAMES: usize = 2;
-    const SG_MAX_COLOR_ATTACHMENTS: usize = 4;
-    const SG_MAX_SHADERSTAGE_BUFFERS: usize = 8;
-    const SG_MAX_SHADERSTAGE_IMAGES: usize = 12;
+    pub const SG_MAX_COLOR_ATTACHMENTS: usize = 4;
+    pub const SG_MAX_SHADERSTAGE_BUFFERS: usize = 8;
+    pub const SG_MAX_SHADERSTAGE_IMAGES: usize = 12;
     const SG_MAX_SHADERSTAGE_UBS: usize = 4;
     const SG_MAX_UB_MEMBERS: usize = 16;
-    const SG_MAX_VERTEX_ATTRIBUTES: usize = 16;
+    pub const SG_MAX_VERTEX_ATTRIBUTES: usize = 16;
     const SG_MAX_MIPMAPS: usize = 16;
     const _SG_MAX_TEXTUREARRAY_LAYERS: usize = 128;
 
     #[repr(C)]
-    #[derive(Debug)]
+    #[derive(Default, Debug)]
     pub struct SgPassAction {
         _start_canary: u32,
-        colors: [super::SgColorAttachmentAction; SG_MAX_COLOR_ATTACHMENTS],
-        depth: super::SgDepthAttachmentAction,
-        stencil: super::SgStencilAttachmentAction,
+        pub(super) colors: [super::SgColorAttachmentAction; SG_MAX_COLOR_ATTACHMENTS],
+        pub(super) depth: super::SgDepthAttachmentAction,
+        pub(super) stencil: super::SgStencilAttachmentAction,
         _end_canary: u32,
     }
 
@@ -60,12 +85,12 @@ mod ffi {
     #[derive(Default, Debug)]
     pub struct SgBindings {
         _start_canary: u32,
-        vertex_buffers: [super::SgBuffer; SG_MAX_SHADERSTAGE_BUFFERS],
-        vertex_buffer_offsets: [c_int; SG_MAX_SHADERSTAGE_BUFFERS],
-        index_buffer: super::SgBuffer,
-        index_buffer_offset: c_int,
-        vs_images: [super::SgImage; SG_MAX_SHADERSTAGE_IMAGES],
-        fs_images: [super::SgImage; SG_MAX_SHADERSTAGE_IMAGES],
+        pub(super) vertex_buffers: [super::SgBuffer; SG_MAX_SHADERSTAGE_BUFFERS],
+        pub(super) vertex_buffer_offsets: [c_int; SG_MAX_SHADERSTAGE_BUFFERS],
+        pub(super) index_buffer: super::SgBuffer,
+        pub(super) index_buffer_offset: c_int,
+        pub(super) vs_images: [super::SgImage; SG_MAX_SHADERSTAGE_IMAGES],
+        pub(super) fs_images: [super::SgImage; SG_MAX_SHADERSTAGE_IMAGES],
         _end_canary: u32,
     }
 
@@ -156,20 +181,14 @@ mod ffi {
     }
 
     impl SgBufferDesc {
-        pub fn make<T>(content: Option<&T>, desc: &super::SgBufferDesc) -> SgBufferDesc {
-            let ptr = if content.is_some() {
-                content.unwrap() as *const T
-            } else {
-                null()
-            };
-
+        pub fn make(content: *const c_void, desc: &super::SgBufferDesc, label: *const c_char) -> SgBufferDesc {
             SgBufferDesc {
                 _start_canary: 0,
                 size: desc.size as c_int,
                 buffer_type: desc.buffer_type,
                 usage: desc.usage,
-                content: ptr as *const c_void,
-                label: null(),
+                content,
+                label,
                 gl_buffers: [0, 0],
                 mtl_buffers: [null(), null()],
                 d3d11_buffer: null(),
@@ -283,7 +302,7 @@ mod ffi {
     }
 
     impl SgImageDesc {
-        pub fn make<T>(content: Option<&[(*const T, i32)]>, desc: &super::SgImageDesc) -> SgImageDesc {
+        pub fn make<T>(content: Option<&[(*const T, i32)]>, desc: &super::SgImageDesc, label: *const c_char) -> SgImageDesc {
             SgImageDesc {
                 _start_canary: 0,
                 image_type: desc.image_type,
@@ -304,7 +323,7 @@ mod ffi {
                 min_lod: desc.min_lod,
                 max_lod: desc.max_lod,
                 content: SgImageContent::make(content),
-                label: null(),
+                label,
                 gl_textures: [0; SG_NUM_INFLIGHT_FRAMES],
                 mtl_textures: [null(); SG_NUM_INFLIGHT_FRAMES],
                 d3d11_texture: null(),
@@ -412,16 +431,7 @@ mod ffi {
     }
 
     impl SgShaderDesc {
-        pub fn make(desc: &super::SgShaderDesc) -> SgShaderDesc {
-            let from_str = |s: Option<&str>| -> *const c_char {
-                if s.is_some() {
-                    let source = s.unwrap();
-                    CString::new(source).unwrap().into_raw()
-                } else {
-                    null()
-                }
-            };
-
+        pub fn make(desc: &super::SgShaderDesc, label: *const c_char, arena: &mut MarshalArena) -> SgShaderDesc {
             let (vs_bytes, vs_size) = Self::collect_bytecode(desc.vs.byte_code);
             let (fs_bytes, fs_size) = Self::collect_bytecode(desc.fs.byte_code);
 
@@ -429,30 +439,30 @@ mod ffi {
                 _start_canary: 0,
                 attrs: Default::default(),
                 vs: SgShaderStageDesc {
-                    source: from_str(desc.vs.source),
+                    source: arena.c_str_opt(desc.vs.source),
                     byte_code: vs_bytes,
                     byte_code_size: vs_size,
-                    entry: from_str(desc.vs.entry),
+                    entry: arena.c_str_opt(desc.vs.entry),
                     ..Default::default()
                 },
                 fs: SgShaderStageDesc {
-                    source: from_str(desc.fs.source),
+                    source: arena.c_str_opt(desc.fs.source),
                     byte_code: fs_bytes,
                     byte_code_size: fs_size,
-                    entry: from_str(desc.fs.entry),
+                    entry: arena.c_str_opt(desc.fs.entry),
                     ..Default::default()
                 },
-                label: null(),
+                label,
                 _end_canary: 0,
             };
 
-            Self::collect_attrs(&mut shd, &desc.attrs);
+            Self::collect_attrs(&mut shd, &desc.attrs, arena);
 
-            Self::collect_uniform_blocks(&mut shd.vs, &desc.vs.uniform_blocks);
-            Self::collect_images(&mut shd.vs, &desc.vs.images);
+            Self::collect_uniform_blocks(&mut shd.vs, &desc.vs.uniform_blocks, arena);
+            Self::collect_images(&mut shd.vs, &desc.vs.images, arena);
 
-            Self::collect_uniform_blocks(&mut shd.fs, &desc.fs.uniform_blocks);
-            Self::collect_images(&mut shd.fs, &desc.fs.images);
+            Self::collect_uniform_blocks(&mut shd.fs, &desc.fs.uniform_blocks, arena);
+            Self::collect_images(&mut shd.fs, &desc.fs.images, arena);
 
             shd
         }
@@ -468,49 +478,46 @@ mod ffi {
         }
 
         fn collect_attrs(desc: &mut SgShaderDesc,
-                         src: &[super::SgShaderAttrDesc]) {
+                         src: &[super::SgShaderAttrDesc],
+                         arena: &mut MarshalArena) {
             for (idx, attr) in src.iter().enumerate() {
-                let name = CString::new(attr.name).unwrap();
-                let sem_name = CString::new(attr.sem_name).unwrap();
-
                 desc.attrs[idx] = SgShaderAttrDesc {
-                    name: name.into_raw(),
-                    sem_name: sem_name.into_raw(),
+                    name: arena.c_str(attr.name),
+                    sem_name: arena.c_str(attr.sem_name),
                     sem_index: attr.sem_index,
                 };
             }
         }
 
         fn collect_uniforms(desc: &mut SgShaderUniformBlockDesc,
-                            src: &[super::SgShaderUniformDesc]) {
+                            src: &[super::SgShaderUniformDesc],
+                            arena: &mut MarshalArena) {
             for (idx, u) in src.iter().enumerate() {
                 let dst = &mut desc.uniforms[idx];
 
-                let name = CString::new(u.name).unwrap();
-
-                dst.name = name.into_raw();
+                dst.name = arena.c_str(u.name);
                 dst.uniform_type = u.uniform_type;
                 dst.array_count = u.array_count;
             }
         }
 
         fn collect_uniform_blocks(desc: &mut SgShaderStageDesc,
-                                  src: &[super::SgShaderUniformBlockDesc]) {
+                                  src: &[super::SgShaderUniformBlockDesc],
+                                  arena: &mut MarshalArena) {
             for (idx, ub) in src.iter().enumerate() {
                 let dst = &mut desc.uniform_blocks[idx];
                 dst.size = ub.size;
-                SgShaderDesc::collect_uniforms(dst, &ub.uniforms);
+                SgShaderDesc::collect_uniforms(dst, &ub.uniforms, arena);
             }
         }
 
         fn collect_images(desc: &mut SgShaderStageDesc,
-                          src: &[super::SgShaderImageDesc]) {
+                          src: &[super::SgShaderImageDesc],
+                          arena: &mut MarshalArena) {
             for (idx, img) in src.iter().enumerate() {
                 let dst = &mut desc.images[idx];
 
-                let name = CString::new(img.name).unwrap();
-
-                dst.name = name.into_raw();
+                dst.name = arena.c_str(img.name);
                 dst.image_type = img.image_type;
             }
         }
@@ -582,7 +589,7 @@ mod ffi {
     }
 
     impl SgPipelineDesc {
-        pub fn make(desc: &super::SgPipelineDesc) -> SgPipelineDesc {
+        pub fn make(desc: &super::SgPipelineDesc, label: *const c_char) -> SgPipelineDesc {
             let blend = desc.blend;
 
             let mut pip = SgPipelineDesc {
@@ -607,7 +614,7 @@ mod ffi {
                     blend_color: blend.blend_color,
                 },
                 rasterizer: (*desc).rasterizer,
-                label: null(),
+                label,
                 _end_canary: 0,
             };
 
@@ -643,20 +650,20 @@ mod ffi {
     #[repr(C)]
     #[derive(Debug)]
     pub struct SgPassDesc {
-        _start_canary: u32,
-        color_attachments: [super::SgAttachmentDesc; SG_MAX_COLOR_ATTACHMENTS],
-        depth_stencil_attachment: super::SgAttachmentDesc,
-        label: *const c_char,
-        _end_canary: u32,
+        pub(super) _start_canary: u32,
+        pub(super) color_attachments: [super::SgAttachmentDesc; SG_MAX_COLOR_ATTACHMENTS],
+        pub(super) depth_stencil_attachment: super::SgAttachmentDesc,
+        pub(super) label: *const c_char,
+        pub(super) _end_canary: u32,
     }
 
     impl SgPassDesc {
-        pub fn make(desc: &super::SgPassDesc) -> SgPassDesc {
+        pub fn make(desc: &super::SgPassDesc, label: *const c_char) -> SgPassDesc {
             let mut pass = SgPassDesc {
                 _start_canary: 0,
                 color_attachments: Default::default(),
                 depth_stencil_attachment: desc.depth_stencil_attachment,
-                label: null(),
+                label,
                 _end_canary: 0,
             };
 
@@ -723,6 +730,10 @@ mod ffi {
         pub fn sg_end_pass();
 
         pub fn sg_commit();
+
+        pub fn sokol_rs_log_clear();
+        pub fn sokol_rs_log_get() -> *const c_char;
+        pub fn sokol_rs_sg_validate_error() -> *const c_char;
     }
 }
 
@@ -802,7 +813,7 @@ pub enum SgFeature {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum SgResourceState {
     Initial,
     Alloc,
@@ -883,7 +894,7 @@ pub enum SgCubeFace {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub enum SgShaderStage {
     Vertex,
     Fragment,
@@ -1224,16 +1235,17 @@ pub struct SgDesc {
 }
 
 #[derive(Default, Debug)]
-pub struct SgBufferDesc {
+pub struct SgBufferDesc<'a> {
     pub size: usize,
     pub buffer_type: SgBufferType,
     pub usage: SgUsage,
+    pub label: &'a str,
 }
 
 pub const SG_BUFFER_CONTENT_NONE: Option<&u8> = None;
 
 #[derive(Default, Debug)]
-pub struct SgImageDesc {
+pub struct SgImageDesc<'a> {
     pub image_type: SgImageType,
     pub render_target: bool,
     pub width: i32,
@@ -1251,6 +1263,7 @@ pub struct SgImageDesc {
     pub max_anisotropy: u32,
     pub min_lod: f32,
     pub max_lod: f32,
+    pub label: &'a str,
 }
 
 pub const SG_IMAGE_CONTENT_NONE: Option<&[(*const u8, i32)]> = None;
@@ -1290,14 +1303,15 @@ pub struct SgShaderStageDesc<'a> {
     pub images: Vec<SgShaderImageDesc<'a>>,
 }
 
-#[derive(Debug)]
+#[derive(Default, Debug)]
 pub struct SgShaderDesc<'a> {
     pub attrs: Vec<SgShaderAttrDesc<'a>>,
     pub vs: SgShaderStageDesc<'a>,
     pub fs: SgShaderStageDesc<'a>,
+    pub label: &'a str,
 }
 
-#[derive(Default, Debug)]
+#[derive(Copy, Clone, Default, Debug)]
 pub struct SgBufferLayoutDesc {
     pub stride: usize,
     pub step_func: SgVertexStep,
@@ -1354,7 +1368,7 @@ pub struct SgRasterizerState {
     pub depth_bias_clamp: f32,
 }
 
-#[derive(Default, Debug)]
+#[derive(Copy, Clone, Default, Debug)]
 pub struct SgVertexAttrDesc {
     pub buffer_index: i32,
     pub offset: i32,
@@ -1368,7 +1382,7 @@ pub struct SgLayoutDesc {
 }
 
 #[derive(Default, Debug)]
-pub struct SgPipelineDesc {
+pub struct SgPipelineDesc<'a> {
     pub shader: SgShader,
     pub layout: SgLayoutDesc,
     pub primitive_type: SgPrimitiveType,
@@ -1376,6 +1390,7 @@ pub struct SgPipelineDesc {
     pub depth_stencil: SgDepthStencilState,
     pub blend: SgBlendState,
     pub rasterizer: SgRasterizerState,
+    pub label: &'a str,
 }
 
 #[repr(C)]
@@ -1415,9 +1430,10 @@ pub struct SgAttachmentDesc {
 }
 
 #[derive(Default, Debug)]
-pub struct SgPassDesc {
+pub struct SgPassDesc<'a> {
     pub color_attachments: Vec<SgAttachmentDesc>,
     pub depth_stencil_attachment: SgAttachmentDesc,
+    pub label: &'a str,
 }
 
 /*
@@ -1428,12 +1444,17 @@ pub fn sg_setup(desc: &SgDesc) {
     unsafe {
         ffi::sg_setup(&ffi::SgDesc::make(desc));
     }
+    resource::begin_context();
 }
 
 pub fn sg_shutdown() {
+    resource::end_context();
     unsafe {
         ffi::sg_shutdown();
     }
+    trace::release_trace_hooks();
+    label::release_labels();
+    content::release_buffer_sizes();
 }
 
 pub fn sg_isvalid() -> bool {
@@ -1468,63 +1489,81 @@ pub fn sg_reset_state_cache() {
 }
 
 pub fn sg_make_buffer<T>(content: Option<&T>, desc: &SgBufferDesc) -> SgBuffer {
-    unsafe {
-        ffi::sg_make_buffer(&ffi::SgBufferDesc::make(content, desc))
-    }
+    let ptr = match content {
+        Some(content) => content as *const T as *const c_void,
+        None => null(),
+    };
+    make_buffer(ptr, desc)
+}
+
+fn make_buffer(content: *const c_void, desc: &SgBufferDesc) -> SgBuffer {
+    let buf = label::make_labeled(ResourceKind::Buffer, desc.label, |label| unsafe {
+        ffi::sg_make_buffer(&ffi::SgBufferDesc::make(content, desc, label))
+    }, |buf| buf.id);
+    content::register_buffer_size(buf, desc.size);
+    buf
 }
 
 pub fn sg_make_image<T>(content: Option<&[(*const T, i32)]>, desc: &SgImageDesc) -> SgImage {
-    unsafe {
-        ffi::sg_make_image(&ffi::SgImageDesc::make(content, desc))
-    }
+    label::make_labeled(ResourceKind::Image, desc.label, |label| unsafe {
+        ffi::sg_make_image(&ffi::SgImageDesc::make(content, desc, label))
+    }, |img| img.id)
 }
 
 pub fn sg_make_shader(desc: &SgShaderDesc) -> SgShader {
-    unsafe {
-        ffi::sg_make_shader(&ffi::SgShaderDesc::make(desc))
-    }
+    label::make_labeled(ResourceKind::Shader, desc.label, |label| unsafe {
+        // sokol_gfx copies all names, and compiles the sources immediately
+        let mut arena = MarshalArena::new();
+        ffi::sg_make_shader(&ffi::SgShaderDesc::make(desc, label, &mut arena))
+    }, |shd| shd.id)
 }
 
 pub fn sg_make_pipeline(desc: &SgPipelineDesc) -> SgPipeline {
-    unsafe {
-        ffi::sg_make_pipeline(&ffi::SgPipelineDesc::make(desc))
-    }
+    label::make_labeled(ResourceKind::Pipeline, desc.label, |label| unsafe {
+        ffi::sg_make_pipeline(&ffi::SgPipelineDesc::make(desc, label))
+    }, |pip| pip.id)
 }
 
 pub fn sg_make_pass(desc: &SgPassDesc) -> SgPass {
-    unsafe {
-        ffi::sg_make_pass(&ffi::SgPassDesc::make(desc))
-    }
+    label::make_labeled(ResourceKind::Pass, desc.label, |label| unsafe {
+        ffi::sg_make_pass(&ffi::SgPassDesc::make(desc, label))
+    }, |pass| pass.id)
 }
 
 pub fn sg_destroy_buffer(buf: SgBuffer) {
     unsafe {
         ffi::sg_destroy_buffer(buf);
     }
+    label::release_label(ResourceKind::Buffer, buf.id);
+    content::release_buffer_size(buf);
 }
 
 pub fn sg_destroy_image(img: SgImage) {
     unsafe {
         ffi::sg_destroy_image(img);
     }
+    label::release_label(ResourceKind::Image, img.id);
 }
 
 pub fn sg_destroy_shader(shd: SgShader) {
     unsafe {
         ffi::sg_destroy_shader(shd);
     }
+    label::release_label(ResourceKind::Shader, shd.id);
 }
 
 pub fn sg_destroy_pipeline(pip: SgPipeline) {
     unsafe {
         ffi::sg_destroy_pipeline(pip);
     }
+    label::release_label(ResourceKind::Pipeline, pip.id);
 }
 
 pub fn sg_destroy_pass(pass: SgPass) {
     unsafe {
         ffi::sg_destroy_pass(pass);
     }
+    label::release_label(ResourceKind::Pass, pass.id);
 }
 
 pub fn sg_update_buffer<T>(buf: SgBuffer, content: &T, content_size: i32) {
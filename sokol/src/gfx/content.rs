@@ -0,0 +1,169 @@
+//! Typed buffer content.
+//!
+//! The functions in this module take buffer content as slices of `Pod` types.
+//! The content size is derived from the slice, and checked against the size
+//! the buffer has been created with.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::os::raw::c_void;
+use std::ptr::null;
+
+use super::*;
+
+/// Marker trait for "plain old data" types, which can be copied to GPU memory
+/// byte by byte.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` (or a primitive type), must not contain
+/// references or pointers, and must not contain padding bytes.
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for i64 {}
+unsafe impl Pod for f32 {}
+unsafe impl Pod for f64 {}
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+thread_local! {
+    static BUFFER_SIZES: RefCell<HashMap<i32, usize>> = RefCell::new(HashMap::new());
+}
+
+pub(crate) fn register_buffer_size(buf: SgBuffer, size: usize) {
+    if buf.id != ffi::SG_INVALID_ID {
+        BUFFER_SIZES.with(|sizes| {
+            sizes.borrow_mut().insert(buf.id, size);
+        });
+    }
+}
+
+pub(crate) fn release_buffer_size(buf: SgBuffer) {
+    BUFFER_SIZES.with(|sizes| {
+        sizes.borrow_mut().remove(&buf.id);
+    });
+}
+
+pub(crate) fn release_buffer_sizes() {
+    BUFFER_SIZES.with(|sizes| {
+        sizes.borrow_mut().clear();
+    });
+}
+
+/// Returns the size in bytes the buffer has been created with.
+pub fn sg_query_buffer_size(buf: SgBuffer) -> Option<usize> {
+    BUFFER_SIZES.with(|sizes| {
+        sizes.borrow().get(&buf.id).cloned()
+    })
+}
+
+fn check_buffer_size(buf: SgBuffer, content_size: usize) -> Result<(), GfxError> {
+    match sg_query_buffer_size(buf) {
+        Some(size) if content_size > size => Err(GfxError::SizeMismatch {
+            kind: ResourceKind::Buffer,
+            size,
+            content_size,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn check_desc_size(desc: &SgBufferDesc, content_size: usize) -> Result<(), GfxError> {
+    if desc.size != 0 && desc.size != content_size {
+        Err(GfxError::SizeMismatch {
+            kind: ResourceKind::Buffer,
+            size: desc.size,
+            content_size,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Creates a buffer initialized with `content`.
+///
+/// If `desc.size` is zero, the buffer size is the size of `content`. Otherwise,
+/// both sizes must match.
+///
+/// sokol_gfx only accepts initial content for immutable buffers. Dynamic and
+/// stream buffers are created empty, then updated with `content`, which
+/// counts as their one update of the current frame.
+pub fn sg_make_buffer_slice<T: Pod>(content: &[T], desc: &SgBufferDesc) -> Result<SgBuffer, GfxError> {
+    let content_size = mem::size_of_val(content);
+    check_desc_size(desc, content_size)?;
+
+    let desc = SgBufferDesc {
+        size: content_size,
+        buffer_type: desc.buffer_type,
+        usage: desc.usage,
+        label: desc.label,
+    };
+
+    match desc.usage {
+        SgUsage::_Default | SgUsage::Immutable => {
+            try_make_buffer(content.as_ptr() as *const c_void, &desc)
+        }
+        SgUsage::Dynamic | SgUsage::Stream => {
+            let buf = try_make_buffer(null(), &desc)?;
+            sg_update_buffer_slice(buf, content)?;
+            Ok(buf)
+        }
+    }
+}
+
+/// Creates an empty dynamic or stream buffer, which can hold `len` values
+/// of type `T`.
+///
+/// If `desc.size` is zero, the buffer size is derived from `len`. Otherwise,
+/// both sizes must match.
+pub fn sg_make_buffer_with_capacity<T: Pod>(len: usize, desc: &SgBufferDesc) -> Result<SgBuffer, GfxError> {
+    let size = len * mem::size_of::<T>();
+    check_desc_size(desc, size)?;
+
+    try_make_buffer(null(), &SgBufferDesc {
+        size,
+        buffer_type: desc.buffer_type,
+        usage: desc.usage,
+        label: desc.label,
+    })
+}
+
+/// Replaces the content of a dynamic or stream buffer.
+pub fn sg_update_buffer_slice<T: Pod>(buf: SgBuffer, content: &[T]) -> Result<(), GfxError> {
+    let content_size = mem::size_of_val(content);
+    check_buffer_size(buf, content_size)?;
+
+    unsafe {
+        ffi::sg_update_buffer(buf, content.as_ptr() as *const c_void, content_size as i32);
+    }
+
+    Ok(())
+}
+
+/// Appends `content` to a dynamic or stream buffer.
+///
+/// Returns the byte offset of the appended content, to be used in
+/// `SgBindings`. sokol_gfx starts each append at a multiple of 4 bytes, so
+/// the offset isn't necessarily a multiple of the size of `T`.
+pub fn sg_append_buffer_slice<T: Pod>(buf: SgBuffer, content: &[T]) -> Result<usize, GfxError> {
+    let content_size = mem::size_of_val(content);
+    check_buffer_size(buf, content_size)?;
+
+    let offset = unsafe {
+        ffi::sg_append_buffer(buf, content.as_ptr() as *const c_void, content_size as i32)
+    };
+
+    if sg_query_buffer_overflow(buf) {
+        return Err(GfxError::BufferOverflow);
+    }
+
+    Ok(offset as usize)
+}
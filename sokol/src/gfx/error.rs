@@ -0,0 +1,182 @@
+//! Fallible resource creation.
+//!
+//! The `sg_make_*()` functions always return a resource id, even if the
+//! resource couldn't be created. The `sg_try_make_*()` variants check the
+//! result, and report the reason of a failure as `GfxError`.
+//!
+//! sokol_gfx validation errors abort the program in debug builds. They are
+//! only reported as `GfxError::ValidationFailed` with the `validate-non-fatal`
+//! feature, which affects all resources, including the ones created with the
+//! `sg_make_*()` functions.
+
+use std::error;
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::c_void;
+
+use super::*;
+
+/// The type of a sokol_gfx resource.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ResourceKind {
+    Buffer,
+    Image,
+    Shader,
+    Pipeline,
+    Pass,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum GfxError {
+    /// The resource pool is exhausted. Pool sizes are configured with `SgDesc`.
+    PoolExhausted(ResourceKind),
+    /// The resource description didn't pass sokol_gfx validation.
+    ///
+    /// Validation is only performed in debug builds, and is fatal unless
+    /// the `validate-non-fatal` feature is enabled.
+    ValidationFailed {
+        kind: ResourceKind,
+        message: String,
+    },
+    /// The backend failed to create the resource. For shaders, `log` contains
+    /// the compile or link errors reported by the 3D API.
+    CreateFailed {
+        kind: ResourceKind,
+        log: String,
+    },
+    /// The size of the content doesn't match the size of the resource.
+    SizeMismatch {
+        kind: ResourceKind,
+        size: usize,
+        content_size: usize,
+    },
+    /// Appended content didn't fit into the remaining space of the buffer.
+    BufferOverflow,
+}
+
+impl fmt::Display for GfxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GfxError::PoolExhausted(kind) => {
+                write!(f, "{:?} pool exhausted", kind)
+            }
+            GfxError::ValidationFailed { kind, message } => {
+                write!(f, "{:?} validation failed: {}", kind, message)
+            }
+            GfxError::CreateFailed { kind, log } => {
+                write!(f, "failed to create {:?}: {}", kind, log)
+            }
+            GfxError::SizeMismatch { kind, size, content_size } => {
+                write!(f, "{:?} size is {} bytes, but content size is {} bytes", kind, size, content_size)
+            }
+            GfxError::BufferOverflow => {
+                write!(f, "buffer overflow")
+            }
+        }
+    }
+}
+
+impl error::Error for GfxError {}
+
+fn captured_log() -> String {
+    unsafe {
+        CStr::from_ptr(ffi::sokol_rs_log_get()).to_string_lossy().trim_end().to_string()
+    }
+}
+
+fn validate_error() -> Option<String> {
+    unsafe {
+        let err = ffi::sokol_rs_sg_validate_error();
+        if err.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr(err).to_string_lossy().into_owned())
+        }
+    }
+}
+
+fn try_make<R: Copy>(kind: ResourceKind,
+                     make: impl FnOnce() -> R,
+                     id: fn(R) -> i32,
+                     query_state: fn(R) -> SgResourceState,
+                     destroy: fn(R)) -> Result<R, GfxError> {
+    unsafe {
+        ffi::sokol_rs_log_clear();
+    }
+
+    let res = make();
+
+    if id(res) == ffi::SG_INVALID_ID {
+        return Err(GfxError::PoolExhausted(kind));
+    }
+
+    if query_state(res) != SgResourceState::Failed {
+        return Ok(res);
+    }
+
+    let log = captured_log();
+
+    let err = match validate_error() {
+        Some(message) => GfxError::ValidationFailed {
+            kind,
+            message: if log.is_empty() { message } else { log },
+        },
+        None => GfxError::CreateFailed {
+            kind,
+            log,
+        },
+    };
+
+    // release the pool slot of the failed resource
+    destroy(res);
+
+    Err(err)
+}
+
+pub fn sg_try_make_buffer<T>(content: Option<&T>, desc: &SgBufferDesc) -> Result<SgBuffer, GfxError> {
+    try_make(ResourceKind::Buffer,
+             || sg_make_buffer(content, desc),
+             |buf| buf.id,
+             sg_query_buffer_state,
+             sg_destroy_buffer)
+}
+
+pub(super) fn try_make_buffer(content: *const c_void, desc: &SgBufferDesc) -> Result<SgBuffer, GfxError> {
+    try_make(ResourceKind::Buffer,
+             || make_buffer(content, desc),
+             |buf| buf.id,
+             sg_query_buffer_state,
+             sg_destroy_buffer)
+}
+
+pub fn sg_try_make_image<T>(content: Option<&[(*const T, i32)]>, desc: &SgImageDesc) -> Result<SgImage, GfxError> {
+    try_make(ResourceKind::Image,
+             || sg_make_image(content, desc),
+             |img| img.id,
+             sg_query_image_state,
+             sg_destroy_image)
+}
+
+pub fn sg_try_make_shader(desc: &SgShaderDesc) -> Result<SgShader, GfxError> {
+    try_make(ResourceKind::Shader,
+             || sg_make_shader(desc),
+             |shd| shd.id,
+             sg_query_shader_state,
+             sg_destroy_shader)
+}
+
+pub fn sg_try_make_pipeline(desc: &SgPipelineDesc) -> Result<SgPipeline, GfxError> {
+    try_make(ResourceKind::Pipeline,
+             || sg_make_pipeline(desc),
+             |pip| pip.id,
+             sg_query_pipeline_state,
+             sg_destroy_pipeline)
+}
+
+pub fn sg_try_make_pass(desc: &SgPassDesc) -> Result<SgPass, GfxError> {
+    try_make(ResourceKind::Pass,
+             || sg_make_pass(desc),
+             |pass| pass.id,
+             sg_query_pass_state,
+             sg_destroy_pass)
+}
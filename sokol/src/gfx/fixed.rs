@@ -0,0 +1,275 @@
+//! Fixed-capacity variants of descriptions with `Vec` fields.
+//!
+//! `SgBindings` and `SgPassAction` are converted into their C layout each time
+//! they are applied. The types in this module store the C layout directly, so
+//! applying them in the draw loop doesn't allocate or copy. Builder methods
+//! return an `SgCapacityError` instead of panicking if sokol's fixed array
+//! sizes are exceeded.
+
+use std::error;
+use std::fmt;
+
+use super::*;
+
+/// Returned if a fixed-size array of a sokol_gfx struct is exceeded.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SgCapacityError {
+    pub array: &'static str,
+    pub capacity: usize,
+}
+
+impl fmt::Display for SgCapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "too many {}, capacity is {}", self.array, self.capacity)
+    }
+}
+
+impl error::Error for SgCapacityError {}
+
+fn check_slot(array: &'static str, slot: usize, capacity: usize) -> Result<(), SgCapacityError> {
+    if slot < capacity {
+        Ok(())
+    } else {
+        Err(SgCapacityError {
+            array,
+            capacity,
+        })
+    }
+}
+
+/// Fixed-capacity variant of `SgBindings`.
+///
+/// ```ignore
+/// let bindings = SgFixedBindings::new()
+///     .vertex_buffer(vbuf)?
+///     .index_buffer(ibuf)
+///     .fs_image(tex)?;
+///
+/// sg_apply_bindings_fixed(&bindings);
+/// ```
+#[derive(Default, Debug)]
+pub struct SgFixedBindings {
+    raw: ffi::SgBindings,
+    num_vertex_buffers: usize,
+    num_vs_images: usize,
+    num_fs_images: usize,
+}
+
+impl SgFixedBindings {
+    pub fn new() -> SgFixedBindings {
+        Default::default()
+    }
+
+    /// Adds a vertex buffer to the next free slot.
+    pub fn vertex_buffer(self, buf: SgBuffer) -> Result<SgFixedBindings, SgCapacityError> {
+        self.vertex_buffer_with_offset(buf, 0)
+    }
+
+    /// Adds a vertex buffer with a start offset to the next free slot.
+    pub fn vertex_buffer_with_offset(mut self, buf: SgBuffer, offset: i32) -> Result<SgFixedBindings, SgCapacityError> {
+        let slot = self.num_vertex_buffers;
+        self.set_vertex_buffer(slot, buf, offset)?;
+        Ok(self)
+    }
+
+    pub fn index_buffer(self, buf: SgBuffer) -> SgFixedBindings {
+        self.index_buffer_with_offset(buf, 0)
+    }
+
+    pub fn index_buffer_with_offset(mut self, buf: SgBuffer, offset: i32) -> SgFixedBindings {
+        self.raw.index_buffer = buf;
+        self.raw.index_buffer_offset = offset;
+        self
+    }
+
+    /// Adds a vertex shader image to the next free slot.
+    pub fn vs_image(mut self, img: SgImage) -> Result<SgFixedBindings, SgCapacityError> {
+        let slot = self.num_vs_images;
+        self.set_vs_image(slot, img)?;
+        Ok(self)
+    }
+
+    /// Adds a fragment shader image to the next free slot.
+    pub fn fs_image(mut self, img: SgImage) -> Result<SgFixedBindings, SgCapacityError> {
+        let slot = self.num_fs_images;
+        self.set_fs_image(slot, img)?;
+        Ok(self)
+    }
+
+    pub fn set_vertex_buffer(&mut self, slot: usize, buf: SgBuffer, offset: i32) -> Result<(), SgCapacityError> {
+        check_slot("vertex buffers", slot, ffi::SG_MAX_SHADERSTAGE_BUFFERS)?;
+        self.raw.vertex_buffers[slot] = buf;
+        self.raw.vertex_buffer_offsets[slot] = offset;
+        self.num_vertex_buffers = self.num_vertex_buffers.max(slot + 1);
+        Ok(())
+    }
+
+    /// Changes the start offset of a vertex buffer, e.g. after `sg_append_buffer()`.
+    pub fn set_vertex_buffer_offset(&mut self, slot: usize, offset: i32) -> Result<(), SgCapacityError> {
+        check_slot("vertex buffers", slot, ffi::SG_MAX_SHADERSTAGE_BUFFERS)?;
+        self.raw.vertex_buffer_offsets[slot] = offset;
+        Ok(())
+    }
+
+    pub fn set_index_buffer_offset(&mut self, offset: i32) {
+        self.raw.index_buffer_offset = offset;
+    }
+
+    pub fn set_vs_image(&mut self, slot: usize, img: SgImage) -> Result<(), SgCapacityError> {
+        check_slot("vertex shader images", slot, ffi::SG_MAX_SHADERSTAGE_IMAGES)?;
+        self.raw.vs_images[slot] = img;
+        self.num_vs_images = self.num_vs_images.max(slot + 1);
+        Ok(())
+    }
+
+    pub fn set_fs_image(&mut self, slot: usize, img: SgImage) -> Result<(), SgCapacityError> {
+        check_slot("fragment shader images", slot, ffi::SG_MAX_SHADERSTAGE_IMAGES)?;
+        self.raw.fs_images[slot] = img;
+        self.num_fs_images = self.num_fs_images.max(slot + 1);
+        Ok(())
+    }
+}
+
+/// Fixed-capacity variant of `SgPassAction`.
+#[derive(Default, Debug)]
+pub struct SgFixedPassAction {
+    raw: ffi::SgPassAction,
+    num_colors: usize,
+}
+
+impl SgFixedPassAction {
+    pub fn new() -> SgFixedPassAction {
+        Default::default()
+    }
+
+    /// Adds the action of the next color attachment.
+    pub fn color(mut self, action: SgColorAttachmentAction) -> Result<SgFixedPassAction, SgCapacityError> {
+        let slot = self.num_colors;
+        self.set_color(slot, action)?;
+        Ok(self)
+    }
+
+    pub fn depth(mut self, action: SgDepthAttachmentAction) -> SgFixedPassAction {
+        self.raw.depth = action;
+        self
+    }
+
+    pub fn stencil(mut self, action: SgStencilAttachmentAction) -> SgFixedPassAction {
+        self.raw.stencil = action;
+        self
+    }
+
+    pub fn set_color(&mut self, slot: usize, action: SgColorAttachmentAction) -> Result<(), SgCapacityError> {
+        check_slot("color attachments", slot, ffi::SG_MAX_COLOR_ATTACHMENTS)?;
+        self.raw.colors[slot] = action;
+        self.num_colors = self.num_colors.max(slot + 1);
+        Ok(())
+    }
+}
+
+/// Fixed-capacity variant of `SgPassDesc`.
+#[derive(Default, Debug)]
+pub struct SgFixedPassDesc<'a> {
+    color_attachments: [SgAttachmentDesc; ffi::SG_MAX_COLOR_ATTACHMENTS],
+    num_color_attachments: usize,
+    depth_stencil_attachment: SgAttachmentDesc,
+    label: &'a str,
+}
+
+impl<'a> SgFixedPassDesc<'a> {
+    pub fn new() -> SgFixedPassDesc<'a> {
+        Default::default()
+    }
+
+    /// Adds the next color attachment.
+    pub fn color_attachment(mut self, att: SgAttachmentDesc) -> Result<SgFixedPassDesc<'a>, SgCapacityError> {
+        let slot = self.num_color_attachments;
+        check_slot("color attachments", slot, ffi::SG_MAX_COLOR_ATTACHMENTS)?;
+        self.color_attachments[slot] = att;
+        self.num_color_attachments += 1;
+        Ok(self)
+    }
+
+    pub fn depth_stencil_attachment(mut self, att: SgAttachmentDesc) -> SgFixedPassDesc<'a> {
+        self.depth_stencil_attachment = att;
+        self
+    }
+
+    pub fn label(mut self, label: &'a str) -> SgFixedPassDesc<'a> {
+        self.label = label;
+        self
+    }
+}
+
+/// Fixed-capacity variant of `SgLayoutDesc`. Converts into a `SgLayoutDesc`
+/// with `into()`, to be used with `SgPipelineDesc`.
+#[derive(Default, Debug)]
+pub struct SgFixedLayoutDesc {
+    buffers: [SgBufferLayoutDesc; ffi::SG_MAX_SHADERSTAGE_BUFFERS],
+    num_buffers: usize,
+    attrs: [SgVertexAttrDesc; ffi::SG_MAX_VERTEX_ATTRIBUTES],
+    num_attrs: usize,
+}
+
+impl SgFixedLayoutDesc {
+    pub fn new() -> SgFixedLayoutDesc {
+        Default::default()
+    }
+
+    /// Adds the layout of the next vertex buffer.
+    pub fn buffer(mut self, buffer: SgBufferLayoutDesc) -> Result<SgFixedLayoutDesc, SgCapacityError> {
+        let slot = self.num_buffers;
+        check_slot("vertex buffer layouts", slot, ffi::SG_MAX_SHADERSTAGE_BUFFERS)?;
+        self.buffers[slot] = buffer;
+        self.num_buffers += 1;
+        Ok(self)
+    }
+
+    /// Adds the next vertex attribute.
+    pub fn attr(mut self, attr: SgVertexAttrDesc) -> Result<SgFixedLayoutDesc, SgCapacityError> {
+        let slot = self.num_attrs;
+        check_slot("vertex attributes", slot, ffi::SG_MAX_VERTEX_ATTRIBUTES)?;
+        self.attrs[slot] = attr;
+        self.num_attrs += 1;
+        Ok(self)
+    }
+}
+
+impl From<SgFixedLayoutDesc> for SgLayoutDesc {
+    fn from(layout: SgFixedLayoutDesc) -> SgLayoutDesc {
+        SgLayoutDesc {
+            buffers: layout.buffers[..layout.num_buffers].to_vec(),
+            attrs: layout.attrs[..layout.num_attrs].to_vec(),
+        }
+    }
+}
+
+pub fn sg_make_pass_fixed(desc: &SgFixedPassDesc) -> SgPass {
+    label::make_labeled(ResourceKind::Pass, desc.label, |label| unsafe {
+        ffi::sg_make_pass(&ffi::SgPassDesc {
+            _start_canary: 0,
+            color_attachments: desc.color_attachments,
+            depth_stencil_attachment: desc.depth_stencil_attachment,
+            label,
+            _end_canary: 0,
+        })
+    }, |pass| pass.id)
+}
+
+pub fn sg_begin_default_pass_fixed(pass_action: &SgFixedPassAction, width: i32, height: i32) {
+    unsafe {
+        ffi::sg_begin_default_pass(&pass_action.raw, width, height);
+    }
+}
+
+pub fn sg_begin_pass_fixed(pass: SgPass, pass_action: &SgFixedPassAction) {
+    unsafe {
+        ffi::sg_begin_pass(pass, &pass_action.raw);
+    }
+}
+
+pub fn sg_apply_bindings_fixed(bindings: &SgFixedBindings) {
+    unsafe {
+        ffi::sg_apply_bindings(&bindings.raw);
+    }
+}
@@ -0,0 +1,54 @@
+//! Debug labels of sokol_gfx resources.
+//!
+//! Labels are passed to sokol_gfx as C strings. They are kept alive until the
+//! resource is destroyed, so that tools like `sokol_gfx_imgui` or installed
+//! trace hooks can refer to them.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::ptr::null;
+
+use super::*;
+
+thread_local! {
+    static LABELS: RefCell<HashMap<(ResourceKind, i32), CString>> = RefCell::new(HashMap::new());
+}
+
+/// Calls `make` with the label as C string, then keeps the label alive
+/// for the lifetime of the resource.
+pub(crate) fn make_labeled<R>(kind: ResourceKind,
+                              label: &str,
+                              make: impl FnOnce(*const c_char) -> R,
+                              id: fn(&R) -> i32) -> R {
+    // labels are a debugging aid, so a nul byte cuts them off instead of failing
+    let label = label.split('\0').next().unwrap_or("");
+    if label.is_empty() {
+        return make(null());
+    }
+
+    let label = CString::new(label).unwrap_or_default();
+    let res = make(label.as_ptr());
+
+    let id = id(&res);
+    if id != ffi::SG_INVALID_ID {
+        LABELS.with(|labels| {
+            labels.borrow_mut().insert((kind, id), label);
+        });
+    }
+
+    res
+}
+
+pub(crate) fn release_label(kind: ResourceKind, id: i32) {
+    LABELS.with(|labels| {
+        labels.borrow_mut().remove(&(kind, id));
+    });
+}
+
+pub(crate) fn release_labels() {
+    LABELS.with(|labels| {
+        labels.borrow_mut().clear();
+    });
+}
@@ -0,0 +1,196 @@
+//! Owning handles to sokol_gfx resources.
+//!
+//! The `Sg*` resource ids are plain integers which must be released manually
+//! with the `sg_destroy_*()` functions. The types in this module take ownership
+//! of such an id, and destroy the resource once they are dropped.
+//!
+//! `sg_shutdown()` releases all resources which are still alive. Owning handles
+//! which outlive it, or which have been created in a previous `sg_setup()` /
+//! `sg_shutdown()` cycle, don't destroy anything when they are dropped.
+
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use super::*;
+
+/// Incremented by each `sg_setup()` and `sg_shutdown()`.
+static CONTEXT_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn begin_context() {
+    CONTEXT_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+pub(crate) fn end_context() {
+    CONTEXT_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+fn context_generation() -> usize {
+    CONTEXT_GENERATION.load(Ordering::SeqCst)
+}
+
+macro_rules! owned_resource {
+    ($(#[$attr:meta])* $name:ident, $raw:ident, $destroy:ident) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        pub struct $name {
+            raw: $raw,
+            generation: usize,
+            // sokol_gfx must only be called from the thread which called sg_setup()
+            _not_send: PhantomData<*const ()>,
+        }
+
+        impl $name {
+            /// Takes ownership of a raw resource id.
+            ///
+            /// # Safety
+            ///
+            /// The id must not be owned by any other handle, and must not be
+            /// destroyed manually afterwards, or the resource is destroyed
+            /// twice. Ids returned by `into_raw()` can be passed back.
+            pub unsafe fn from_raw(raw: $raw) -> $name {
+                $name::from_new(raw)
+            }
+
+            /// Takes ownership of a resource id which has just been created.
+            fn from_new(raw: $raw) -> $name {
+                $name {
+                    raw,
+                    generation: context_generation(),
+                    _not_send: PhantomData,
+                }
+            }
+
+            /// Returns the raw resource id. The resource is still owned by this handle.
+            pub fn raw(&self) -> $raw {
+                self.raw
+            }
+
+            /// Releases ownership and returns the raw resource id, which then
+            /// must be destroyed manually.
+            pub fn into_raw(self) -> $raw {
+                let raw = self.raw;
+                mem::forget(self);
+                raw
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                if self.generation == context_generation() {
+                    $destroy(self.raw);
+                }
+            }
+        }
+    }
+}
+
+owned_resource!(
+    /// Owning handle to a `SgBuffer`.
+    Buffer, SgBuffer, sg_destroy_buffer
+);
+
+owned_resource!(
+    /// Owning handle to a `SgImage`.
+    Image, SgImage, sg_destroy_image
+);
+
+owned_resource!(
+    /// Owning handle to a `SgShader`.
+    Shader, SgShader, sg_destroy_shader
+);
+
+owned_resource!(
+    /// Owning handle to a `SgPipeline`.
+    ///
+    /// The pipeline doesn't keep its shader alive.
+    Pipeline, SgPipeline, sg_destroy_pipeline
+);
+
+owned_resource!(
+    /// Owning handle to a `SgPass`.
+    ///
+    /// The pass doesn't keep its attachment images alive.
+    Pass, SgPass, sg_destroy_pass
+);
+
+impl Buffer {
+    /// Creates a buffer, see `sg_make_buffer()`.
+    pub fn new<T>(content: Option<&T>, desc: &SgBufferDesc) -> Buffer {
+        Buffer::from_new(sg_make_buffer(content, desc))
+    }
+
+    /// Creates a buffer, or returns the reason of failure.
+    pub fn try_new<T>(content: Option<&T>, desc: &SgBufferDesc) -> Result<Buffer, GfxError> {
+        sg_try_make_buffer(content, desc).map(Buffer::from_new)
+    }
+
+    /// Creates a buffer initialized with `content`, see `sg_make_buffer_slice()`.
+    pub fn from_slice<T: Pod>(content: &[T], desc: &SgBufferDesc) -> Result<Buffer, GfxError> {
+        sg_make_buffer_slice(content, desc).map(Buffer::from_new)
+    }
+
+    /// Creates an empty buffer, see `sg_make_buffer_with_capacity()`.
+    pub fn with_capacity<T: Pod>(len: usize, desc: &SgBufferDesc) -> Result<Buffer, GfxError> {
+        sg_make_buffer_with_capacity::<T>(len, desc).map(Buffer::from_new)
+    }
+
+    /// See `sg_update_buffer_slice()`.
+    pub fn update<T: Pod>(&self, content: &[T]) -> Result<(), GfxError> {
+        sg_update_buffer_slice(self.raw, content)
+    }
+
+    /// See `sg_append_buffer_slice()`.
+    pub fn append<T: Pod>(&self, content: &[T]) -> Result<usize, GfxError> {
+        sg_append_buffer_slice(self.raw, content)
+    }
+}
+
+impl Image {
+    /// Creates an image, see `sg_make_image()`.
+    pub fn new<T>(content: Option<&[(*const T, i32)]>, desc: &SgImageDesc) -> Image {
+        Image::from_new(sg_make_image(content, desc))
+    }
+
+    /// Creates an image, or returns the reason of failure.
+    pub fn try_new<T>(content: Option<&[(*const T, i32)]>, desc: &SgImageDesc) -> Result<Image, GfxError> {
+        sg_try_make_image(content, desc).map(Image::from_new)
+    }
+}
+
+impl Shader {
+    /// Creates a shader, see `sg_make_shader()`.
+    pub fn new(desc: &SgShaderDesc) -> Shader {
+        Shader::from_new(sg_make_shader(desc))
+    }
+
+    /// Creates a shader, or returns the reason of failure.
+    pub fn try_new(desc: &SgShaderDesc) -> Result<Shader, GfxError> {
+        sg_try_make_shader(desc).map(Shader::from_new)
+    }
+}
+
+impl Pipeline {
+    /// Creates a pipeline, see `sg_make_pipeline()`.
+    pub fn new(desc: &SgPipelineDesc) -> Pipeline {
+        Pipeline::from_new(sg_make_pipeline(desc))
+    }
+
+    /// Creates a pipeline, or returns the reason of failure.
+    pub fn try_new(desc: &SgPipelineDesc) -> Result<Pipeline, GfxError> {
+        sg_try_make_pipeline(desc).map(Pipeline::from_new)
+    }
+}
+
+impl Pass {
+    /// Creates a render pass, see `sg_make_pass()`.
+    pub fn new(desc: &SgPassDesc) -> Pass {
+        Pass::from_new(sg_make_pass(desc))
+    }
+
+    /// Creates a render pass, or returns the reason of failure.
+    pub fn try_new(desc: &SgPassDesc) -> Result<Pass, GfxError> {
+        sg_try_make_pass(desc).map(Pass::from_new)
+    }
+}
@@ -0,0 +1,331 @@
+//! sokol_gfx trace hooks
+//!
+//! `sg_install_trace_hooks()` forwards every traced sokol_gfx call to an
+//! implementation of the `GfxTraceHooks` trait. Hooks installed before, e.g.
+//! by `sokol_gfx_imgui`, are chained and still called.
+//!
+//! Trace hooks are called from inside sokol_gfx. They must not call any
+//! sokol_gfx functions themselves.
+//!
+//! A panic in a trace hook is caught, as it must not unwind through sokol_gfx.
+//! The hooks which panicked aren't called again, and the panic is passed on
+//! once the application callback which called into sokol_gfx returns, e.g.
+//! `sapp_run()` returns it as `SAppPanic`.
+
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::os::raw::c_void;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::ptr::null_mut;
+use std::slice;
+
+use super::*;
+use crate::app::PanicPayload;
+
+mod ffi {
+    use std::os::raw::c_char;
+    use std::os::raw::c_int;
+    use std::os::raw::c_void;
+
+    use super::super::*;
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct SgTraceHooks {
+        pub user_data: *mut c_void,
+        pub reset_state_cache: Option<extern fn(*mut c_void)>,
+        pub make_buffer: Option<extern fn(*const c_void, SgBuffer, *mut c_void)>,
+        pub make_image: Option<extern fn(*const c_void, SgImage, *mut c_void)>,
+        pub make_shader: Option<extern fn(*const c_void, SgShader, *mut c_void)>,
+        pub make_pipeline: Option<extern fn(*const c_void, SgPipeline, *mut c_void)>,
+        pub make_pass: Option<extern fn(*const c_void, SgPass, *mut c_void)>,
+        pub destroy_buffer: Option<extern fn(SgBuffer, *mut c_void)>,
+        pub destroy_image: Option<extern fn(SgImage, *mut c_void)>,
+        pub destroy_shader: Option<extern fn(SgShader, *mut c_void)>,
+        pub destroy_pipeline: Option<extern fn(SgPipeline, *mut c_void)>,
+        pub destroy_pass: Option<extern fn(SgPass, *mut c_void)>,
+        pub update_buffer: Option<extern fn(SgBuffer, *const c_void, c_int, *mut c_void)>,
+        pub update_image: Option<extern fn(SgImage, *const c_void, *mut c_void)>,
+        pub append_buffer: Option<extern fn(SgBuffer, *const c_void, c_int, c_int, *mut c_void)>,
+        pub begin_default_pass: Option<extern fn(*const c_void, c_int, c_int, *mut c_void)>,
+        pub begin_pass: Option<extern fn(SgPass, *const c_void, *mut c_void)>,
+        pub apply_viewport: Option<extern fn(c_int, c_int, c_int, c_int, bool, *mut c_void)>,
+        pub apply_scissor_rect: Option<extern fn(c_int, c_int, c_int, c_int, bool, *mut c_void)>,
+        pub apply_pipeline: Option<extern fn(SgPipeline, *mut c_void)>,
+        pub apply_bindings: Option<extern fn(*const c_void, *mut c_void)>,
+        pub apply_uniforms: Option<extern fn(SgShaderStage, c_int, *const c_void, c_int, *mut c_void)>,
+        pub draw: Option<extern fn(c_int, c_int, c_int, *mut c_void)>,
+        pub end_pass: Option<extern fn(*mut c_void)>,
+        pub commit: Option<extern fn(*mut c_void)>,
+        pub alloc_buffer: Option<extern fn(SgBuffer, *mut c_void)>,
+        pub alloc_image: Option<extern fn(SgImage, *mut c_void)>,
+        pub alloc_shader: Option<extern fn(SgShader, *mut c_void)>,
+        pub alloc_pipeline: Option<extern fn(SgPipeline, *mut c_void)>,
+        pub alloc_pass: Option<extern fn(SgPass, *mut c_void)>,
+        pub init_buffer: Option<extern fn(SgBuffer, *const c_void, *mut c_void)>,
+        pub init_image: Option<extern fn(SgImage, *const c_void, *mut c_void)>,
+        pub init_shader: Option<extern fn(SgShader, *const c_void, *mut c_void)>,
+        pub init_pipeline: Option<extern fn(SgPipeline, *const c_void, *mut c_void)>,
+        pub init_pass: Option<extern fn(SgPass, *const c_void, *mut c_void)>,
+        pub fail_buffer: Option<extern fn(SgBuffer, *mut c_void)>,
+        pub fail_image: Option<extern fn(SgImage, *mut c_void)>,
+        pub fail_shader: Option<extern fn(SgShader, *mut c_void)>,
+        pub fail_pipeline: Option<extern fn(SgPipeline, *mut c_void)>,
+        pub fail_pass: Option<extern fn(SgPass, *mut c_void)>,
+        pub push_debug_group: Option<extern fn(*const c_char, *mut c_void)>,
+        pub pop_debug_group: Option<extern fn(*mut c_void)>,
+        pub err_buffer_pool_exhausted: Option<extern fn(*mut c_void)>,
+        pub err_image_pool_exhausted: Option<extern fn(*mut c_void)>,
+        pub err_shader_pool_exhausted: Option<extern fn(*mut c_void)>,
+        pub err_pipeline_pool_exhausted: Option<extern fn(*mut c_void)>,
+        pub err_pass_pool_exhausted: Option<extern fn(*mut c_void)>,
+        pub err_context_mismatch: Option<extern fn(*mut c_void)>,
+        pub err_pass_invalid: Option<extern fn(*mut c_void)>,
+        pub err_draw_invalid: Option<extern fn(*mut c_void)>,
+        pub err_bindings_invalid: Option<extern fn(*mut c_void)>,
+    }
+
+    extern {
+        pub fn sg_install_trace_hooks(trace_hooks: *const SgTraceHooks) -> SgTraceHooks;
+    }
+}
+
+/// Receives sokol_gfx trace events.
+///
+/// All functions have an empty default implementation, so that implementations
+/// only need to override the calls they are interested in.
+#[allow(unused_variables)]
+pub trait GfxTraceHooks {
+    fn reset_state_cache(&mut self) {}
+
+    fn make_buffer(&mut self, result: SgBuffer) {}
+    fn make_image(&mut self, result: SgImage) {}
+    fn make_shader(&mut self, result: SgShader) {}
+    fn make_pipeline(&mut self, result: SgPipeline) {}
+    fn make_pass(&mut self, result: SgPass) {}
+
+    fn destroy_buffer(&mut self, buf: SgBuffer) {}
+    fn destroy_image(&mut self, img: SgImage) {}
+    fn destroy_shader(&mut self, shd: SgShader) {}
+    fn destroy_pipeline(&mut self, pip: SgPipeline) {}
+    fn destroy_pass(&mut self, pass: SgPass) {}
+
+    fn update_buffer(&mut self, buf: SgBuffer, data: &[u8]) {}
+    fn update_image(&mut self, img: SgImage) {}
+    fn append_buffer(&mut self, buf: SgBuffer, data: &[u8], result: i32) {}
+
+    fn begin_default_pass(&mut self, width: i32, height: i32) {}
+    fn begin_pass(&mut self, pass: SgPass) {}
+    fn apply_viewport(&mut self, x: i32, y: i32, width: i32, height: i32, origin_top_left: bool) {}
+    fn apply_scissor_rect(&mut self, x: i32, y: i32, width: i32, height: i32, origin_top_left: bool) {}
+    fn apply_pipeline(&mut self, pip: SgPipeline) {}
+    fn apply_bindings(&mut self) {}
+    fn apply_uniforms(&mut self, stage: SgShaderStage, ub_index: i32, data: &[u8]) {}
+    fn draw(&mut self, base_element: i32, num_elements: i32, num_instances: i32) {}
+    fn end_pass(&mut self) {}
+    fn commit(&mut self) {}
+
+    fn alloc_buffer(&mut self, result: SgBuffer) {}
+    fn alloc_image(&mut self, result: SgImage) {}
+    fn alloc_shader(&mut self, result: SgShader) {}
+    fn alloc_pipeline(&mut self, result: SgPipeline) {}
+    fn alloc_pass(&mut self, result: SgPass) {}
+
+    fn init_buffer(&mut self, buf: SgBuffer) {}
+    fn init_image(&mut self, img: SgImage) {}
+    fn init_shader(&mut self, shd: SgShader) {}
+    fn init_pipeline(&mut self, pip: SgPipeline) {}
+    fn init_pass(&mut self, pass: SgPass) {}
+
+    fn fail_buffer(&mut self, buf: SgBuffer) {}
+    fn fail_image(&mut self, img: SgImage) {}
+    fn fail_shader(&mut self, shd: SgShader) {}
+    fn fail_pipeline(&mut self, pip: SgPipeline) {}
+    fn fail_pass(&mut self, pass: SgPass) {}
+
+    fn push_debug_group(&mut self, name: &str) {}
+    fn pop_debug_group(&mut self) {}
+
+    fn err_buffer_pool_exhausted(&mut self) {}
+    fn err_image_pool_exhausted(&mut self) {}
+    fn err_shader_pool_exhausted(&mut self) {}
+    fn err_pipeline_pool_exhausted(&mut self) {}
+    fn err_pass_pool_exhausted(&mut self) {}
+    fn err_context_mismatch(&mut self) {}
+    fn err_pass_invalid(&mut self) {}
+    fn err_draw_invalid(&mut self) {}
+    fn err_bindings_invalid(&mut self) {}
+}
+
+struct TraceHooksChain {
+    hooks: Box<dyn GfxTraceHooks>,
+    prev: ffi::SgTraceHooks,
+    panicked: bool,
+}
+
+thread_local! {
+    // boxed, as their addresses are passed to sokol_gfx
+    #[allow(clippy::vec_box)]
+    static INSTALLED_HOOKS: RefCell<Vec<Box<TraceHooksChain>>> = const { RefCell::new(Vec::new()) };
+
+    static TRACE_HOOKS_PANIC: RefCell<Option<PanicPayload>> = const { RefCell::new(None) };
+}
+
+/// Stores the payload of the first panic in a trace hook.
+fn store_panic(payload: PanicPayload) {
+    TRACE_HOOKS_PANIC.with(|panic| {
+        let mut panic = panic.borrow_mut();
+        if panic.is_none() {
+            *panic = Some(payload);
+        }
+    });
+}
+
+/// Returns the payload of a panic caught in a trace hook, if any.
+pub(crate) fn take_trace_hooks_panic() -> Option<PanicPayload> {
+    TRACE_HOOKS_PANIC.with(|panic| panic.borrow_mut().take())
+}
+
+fn bytes<'a>(data: *const c_void, size: c_int) -> &'a [u8] {
+    if data.is_null() || size <= 0 {
+        &[]
+    } else {
+        unsafe {
+            slice::from_raw_parts(data as *const u8, size as usize)
+        }
+    }
+}
+
+fn string<'a>(s: *const c_char) -> std::borrow::Cow<'a, str> {
+    if s.is_null() {
+        "".into()
+    } else {
+        unsafe {
+            CStr::from_ptr(s).to_string_lossy()
+        }
+    }
+}
+
+//
+// Each trampoline calls the Rust implementation, then forwards the call to
+// the hooks which have been installed before. Panics are caught, and the
+// Rust implementation is skipped from then on.
+//
+macro_rules! trampolines {
+    ($($name:ident($($arg:ident: $ty:ty),*) => |$hooks:ident| $call:expr;)*) => {
+        $(
+            extern fn $name($($arg: $ty,)* user_data: *mut c_void) {
+                let chain = unsafe {
+                    &mut *(user_data as *mut TraceHooksChain)
+                };
+
+                if !chain.panicked {
+                    let $hooks = &mut *chain.hooks;
+                    let result = catch_unwind(AssertUnwindSafe(|| {
+                        $call;
+                    }));
+
+                    if let Err(payload) = result {
+                        chain.panicked = true;
+                        store_panic(payload);
+                    }
+                }
+
+                if let Some(prev) = chain.prev.$name {
+                    prev($($arg,)* chain.prev.user_data);
+                }
+            }
+        )*
+
+        fn make_trace_hooks(user_data: *mut c_void) -> ffi::SgTraceHooks {
+            ffi::SgTraceHooks {
+                user_data,
+                $($name: Some($name),)*
+            }
+        }
+    }
+}
+
+trampolines! {
+    reset_state_cache() => |h| h.reset_state_cache();
+    make_buffer(_desc: *const c_void, result: SgBuffer) => |h| h.make_buffer(result);
+    make_image(_desc: *const c_void, result: SgImage) => |h| h.make_image(result);
+    make_shader(_desc: *const c_void, result: SgShader) => |h| h.make_shader(result);
+    make_pipeline(_desc: *const c_void, result: SgPipeline) => |h| h.make_pipeline(result);
+    make_pass(_desc: *const c_void, result: SgPass) => |h| h.make_pass(result);
+    destroy_buffer(buf: SgBuffer) => |h| h.destroy_buffer(buf);
+    destroy_image(img: SgImage) => |h| h.destroy_image(img);
+    destroy_shader(shd: SgShader) => |h| h.destroy_shader(shd);
+    destroy_pipeline(pip: SgPipeline) => |h| h.destroy_pipeline(pip);
+    destroy_pass(pass: SgPass) => |h| h.destroy_pass(pass);
+    update_buffer(buf: SgBuffer, data: *const c_void, size: c_int) => |h| h.update_buffer(buf, bytes(data, size));
+    update_image(img: SgImage, _data: *const c_void) => |h| h.update_image(img);
+    append_buffer(buf: SgBuffer, data: *const c_void, size: c_int, result: c_int) => |h| h.append_buffer(buf, bytes(data, size), result);
+    begin_default_pass(_pass_action: *const c_void, width: c_int, height: c_int) => |h| h.begin_default_pass(width, height);
+    begin_pass(pass: SgPass, _pass_action: *const c_void) => |h| h.begin_pass(pass);
+    apply_viewport(x: c_int, y: c_int, width: c_int, height: c_int, origin_top_left: bool) => |h| h.apply_viewport(x, y, width, height, origin_top_left);
+    apply_scissor_rect(x: c_int, y: c_int, width: c_int, height: c_int, origin_top_left: bool) => |h| h.apply_scissor_rect(x, y, width, height, origin_top_left);
+    apply_pipeline(pip: SgPipeline) => |h| h.apply_pipeline(pip);
+    apply_bindings(_bindings: *const c_void) => |h| h.apply_bindings();
+    apply_uniforms(stage: SgShaderStage, ub_index: c_int, data: *const c_void, num_bytes: c_int) => |h| h.apply_uniforms(stage, ub_index, bytes(data, num_bytes));
+    draw(base_element: c_int, num_elements: c_int, num_instances: c_int) => |h| h.draw(base_element, num_elements, num_instances);
+    end_pass() => |h| h.end_pass();
+    commit() => |h| h.commit();
+    alloc_buffer(result: SgBuffer) => |h| h.alloc_buffer(result);
+    alloc_image(result: SgImage) => |h| h.alloc_image(result);
+    alloc_shader(result: SgShader) => |h| h.alloc_shader(result);
+    alloc_pipeline(result: SgPipeline) => |h| h.alloc_pipeline(result);
+    alloc_pass(result: SgPass) => |h| h.alloc_pass(result);
+    init_buffer(buf: SgBuffer, _desc: *const c_void) => |h| h.init_buffer(buf);
+    init_image(img: SgImage, _desc: *const c_void) => |h| h.init_image(img);
+    init_shader(shd: SgShader, _desc: *const c_void) => |h| h.init_shader(shd);
+    init_pipeline(pip: SgPipeline, _desc: *const c_void) => |h| h.init_pipeline(pip);
+    init_pass(pass: SgPass, _desc: *const c_void) => |h| h.init_pass(pass);
+    fail_buffer(buf: SgBuffer) => |h| h.fail_buffer(buf);
+    fail_image(img: SgImage) => |h| h.fail_image(img);
+    fail_shader(shd: SgShader) => |h| h.fail_shader(shd);
+    fail_pipeline(pip: SgPipeline) => |h| h.fail_pipeline(pip);
+    fail_pass(pass: SgPass) => |h| h.fail_pass(pass);
+    push_debug_group(name: *const c_char) => |h| h.push_debug_group(&string(name));
+    pop_debug_group() => |h| h.pop_debug_group();
+    err_buffer_pool_exhausted() => |h| h.err_buffer_pool_exhausted();
+    err_image_pool_exhausted() => |h| h.err_image_pool_exhausted();
+    err_shader_pool_exhausted() => |h| h.err_shader_pool_exhausted();
+    err_pipeline_pool_exhausted() => |h| h.err_pipeline_pool_exhausted();
+    err_pass_pool_exhausted() => |h| h.err_pass_pool_exhausted();
+    err_context_mismatch() => |h| h.err_context_mismatch();
+    err_pass_invalid() => |h| h.err_pass_invalid();
+    err_draw_invalid() => |h| h.err_draw_invalid();
+    err_bindings_invalid() => |h| h.err_bindings_invalid();
+}
+
+/// Installs trace hooks. Must be called after `sg_setup()`.
+///
+/// Hooks can be installed multiple times. They are called in reverse order of
+/// installation. All hooks are released by `sg_shutdown()`.
+pub fn sg_install_trace_hooks<H: GfxTraceHooks + 'static>(hooks: H) {
+    let mut chain = Box::new(TraceHooksChain {
+        hooks: Box::new(hooks),
+        prev: make_trace_hooks(null_mut()),
+        panicked: false,
+    });
+
+    let trace_hooks = make_trace_hooks(&mut *chain as *mut TraceHooksChain as *mut c_void);
+
+    chain.prev = unsafe {
+        ffi::sg_install_trace_hooks(&trace_hooks)
+    };
+
+    INSTALLED_HOOKS.with(|installed| {
+        installed.borrow_mut().push(chain);
+    });
+}
+
+pub(crate) fn release_trace_hooks() {
+    INSTALLED_HOOKS.with(|installed| {
+        installed.borrow_mut().clear();
+    });
+}
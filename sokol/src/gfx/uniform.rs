@@ -0,0 +1,72 @@
+//! Typed uniform blocks.
+//!
+//! With the `derive` feature, `#[derive(UniformBlock)]` implements the
+//! `UniformBlock` trait for `#[repr(C)]` structs:
+//!
+//! ```ignore
+//! #[repr(C)]
+//! #[derive(Copy, Clone, UniformBlock)]
+//! struct VsParams {
+//!     mvp: [[f32; 4]; 4],
+//!     #[uniform(array)]
+//!     lights: [[f32; 4]; 8],
+//!     #[uniform(name = "time")]
+//!     t: f32,
+//! }
+//! ```
+//!
+//! The derive checks at compile time that the fields follow the packed
+//! uniform layout of sokol_gfx, as expected by the GL backends, which upload
+//! each uniform separately: every uniform starts right after the previous
+//! one. This is not the std140 layout, so `[f32; 3]` isn't aligned to 16
+//! bytes, and arrays have no 16-byte stride. Uniform blocks declared in
+//! HLSL or Metal shaders need to match this layout, e.g. by avoiding `float3`
+//! members or by padding them explicitly.
+
+use std::mem;
+use std::os::raw::c_void;
+
+use super::*;
+
+/// Maps the type of a uniform block struct field to its uniform type.
+pub trait Uniform: Pod {
+    const TYPE: SgUniformType;
+}
+
+macro_rules! uniform {
+    ($ty:ty, $uniform_type:ident) => {
+        impl Uniform for $ty {
+            const TYPE: SgUniformType = SgUniformType::$uniform_type;
+        }
+    }
+}
+
+uniform!(f32, Float);
+uniform!([f32; 2], Float2);
+uniform!([f32; 3], Float3);
+uniform!([f32; 4], Float4);
+uniform!([[f32; 4]; 4], Mat4);
+
+/// A uniform block struct, usually implemented with `#[derive(UniformBlock)]`.
+pub trait UniformBlock: Pod {
+    /// Returns the uniforms of the block, in memory order.
+    fn uniforms() -> Vec<SgShaderUniformDesc<'static>>;
+
+    /// Returns the description of the uniform block, to be used with `SgShaderStageDesc`.
+    fn desc() -> SgShaderUniformBlockDesc<'static> {
+        SgShaderUniformBlockDesc {
+            size: mem::size_of::<Self>() as i32,
+            uniforms: Self::uniforms(),
+        }
+    }
+}
+
+/// Like `sg_apply_uniforms()`, with the size of the uniform block taken from `T`.
+pub fn sg_apply_uniform_block<T: UniformBlock>(stage: SgShaderStage, ub_index: i32, data: &T) {
+    unsafe {
+        ffi::sg_apply_uniforms(stage,
+                               ub_index,
+                               data as *const T as *const c_void,
+                               mem::size_of::<T>() as i32);
+    }
+}
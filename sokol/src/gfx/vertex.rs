@@ -0,0 +1,71 @@
+//! Typed vertex layouts.
+//!
+//! With the `derive` feature, `#[derive(Vertex)]` implements the `Vertex` trait
+//! for `#[repr(C)]` structs:
+//!
+//! ```ignore
+//! #[repr(C)]
+//! #[derive(Copy, Clone, Vertex)]
+//! struct PosColor {
+//!     pos: [f32; 3],
+//!     #[vertex(normalized)]
+//!     color: [u8; 4],
+//! }
+//!
+//! let layout = PosColor::layout(0);
+//! ```
+
+use std::mem;
+
+use super::*;
+
+/// Maps the type of a vertex struct field to its vertex format.
+pub trait VertexAttribute: Pod {
+    const FORMAT: SgVertexFormat;
+}
+
+/// Maps the type of a vertex struct field to its normalized vertex format.
+pub trait NormalizedVertexAttribute: Pod {
+    const FORMAT: SgVertexFormat;
+}
+
+macro_rules! vertex_attribute {
+    ($trait:ident, $ty:ty, $format:ident) => {
+        impl $trait for $ty {
+            const FORMAT: SgVertexFormat = SgVertexFormat::$format;
+        }
+    }
+}
+
+vertex_attribute!(VertexAttribute, f32, Float);
+vertex_attribute!(VertexAttribute, [f32; 2], Float2);
+vertex_attribute!(VertexAttribute, [f32; 3], Float3);
+vertex_attribute!(VertexAttribute, [f32; 4], Float4);
+vertex_attribute!(VertexAttribute, [i8; 4], Byte4);
+vertex_attribute!(VertexAttribute, [u8; 4], UByte4);
+vertex_attribute!(VertexAttribute, [i16; 2], Short2);
+vertex_attribute!(VertexAttribute, [i16; 4], Short4);
+
+vertex_attribute!(NormalizedVertexAttribute, [i8; 4], Byte4N);
+vertex_attribute!(NormalizedVertexAttribute, [u8; 4], UByte4N);
+vertex_attribute!(NormalizedVertexAttribute, [i16; 2], Short2N);
+vertex_attribute!(NormalizedVertexAttribute, [i16; 4], Short4N);
+vertex_attribute!(NormalizedVertexAttribute, u32, UInt10N2);
+
+/// A vertex struct, usually implemented with `#[derive(Vertex)]`.
+pub trait Vertex: Pod {
+    /// Returns the vertex attributes, read from the vertex buffer bound to `buffer_index`.
+    fn attrs(buffer_index: i32) -> Vec<SgVertexAttrDesc>;
+
+    /// Returns the vertex layout, with this struct as the only content of
+    /// the vertex buffer bound to `buffer_index`.
+    fn layout(buffer_index: i32) -> SgLayoutDesc {
+        let mut buffers: Vec<SgBufferLayoutDesc> = vec![Default::default(); buffer_index as usize + 1];
+        buffers[buffer_index as usize].stride = mem::size_of::<Self>();
+
+        SgLayoutDesc {
+            buffers,
+            attrs: Self::attrs(buffer_index),
+        }
+    }
+}
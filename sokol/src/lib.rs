@@ -56,7 +56,7 @@ fn main() {
     sapp_run(app, SAppDesc {
         window_title: "Example".to_string(),
         ..Default::default()
-    });
+    }).unwrap_or_else(|panic| panic.resume());
 }
 ```
 */
@@ -75,4 +75,5 @@ macro_rules! cstr {
 pub mod app;
 pub mod audio;
 pub mod gfx;
+pub(crate) mod marshal;
 pub mod time;
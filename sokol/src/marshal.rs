@@ -0,0 +1,42 @@
+//! Marshalling of Rust descriptions into sokol's C structs.
+//!
+//! C structs passed to sokol refer to strings by pointer. A `MarshalArena`
+//! owns the C string copies of these strings, and must be kept alive as long
+//! as the C side may access them: for the duration of the FFI call, or longer
+//! if the C library keeps the pointer around.
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::ptr::null;
+
+#[derive(Default, Debug)]
+pub(crate) struct MarshalArena {
+    strings: Vec<CString>,
+}
+
+impl MarshalArena {
+    pub(crate) fn new() -> MarshalArena {
+        MarshalArena {
+            strings: Vec::new(),
+        }
+    }
+
+    /// Returns a pointer to a C string copy of `s`, owned by the arena.
+    ///
+    /// The copy ends at the first nul byte of `s`, where C would see the
+    /// end of the string anyway.
+    pub(crate) fn c_str(&mut self, s: &str) -> *const c_char {
+        let s = CString::new(s.split('\0').next().unwrap_or("")).unwrap_or_default();
+        let ptr = s.as_ptr();
+        self.strings.push(s);
+        ptr
+    }
+
+    /// Like `c_str()`, but returns a null pointer for `None`.
+    pub(crate) fn c_str_opt(&mut self, s: Option<&str>) -> *const c_char {
+        match s {
+            Some(s) => self.c_str(s),
+            None => null(),
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //! A Rust API to the [sokol_time.h](https://github.com/floooh/sokol/blob/master/sokol_time.h)
 //! header-only C library.
 
+use crate::app::FrameTimer;
+
 mod ffi {
     extern {
         pub fn stm_setup();
@@ -70,3 +72,149 @@ pub fn stm_ns(ticks: u64) -> f64 {
         ffi::stm_ns(ticks)
     }
 }
+
+/// A fixed-timestep update loop.
+///
+/// Call `advance()` once per frame. It accumulates the time elapsed since
+/// the last call, and calls `update` once for each full time step. Rendering
+/// can interpolate between the last two updates with `alpha()`.
+///
+/// After a stall, e.g. when the window was dragged or the process stopped
+/// in a debugger, at most `max_steps` updates are run, and the remaining
+/// time is dropped. This prevents a slow update from falling further and
+/// further behind.
+///
+/// `stm_setup()` must be called before the first call to `advance()`.
+#[derive(Clone, Debug)]
+pub struct FixedStep {
+    step: f64,
+    max_steps: u32,
+    timer: FrameTimer,
+    accumulator: f64,
+}
+
+impl Default for FixedStep {
+    /// 60 updates per second, with at most 5 updates per frame.
+    fn default() -> Self {
+        FixedStep::new(1.0 / 60.0)
+    }
+}
+
+impl FixedStep {
+    /// Creates a loop with a time step of `step` seconds.
+    pub fn new(step: f64) -> FixedStep {
+        assert!(step > 0.0, "time step must be positive");
+
+        FixedStep {
+            step,
+            max_steps: 5,
+            timer: FrameTimer::new(),
+            accumulator: 0.0,
+        }
+    }
+
+    /// Sets the maximum number of updates per frame.
+    pub fn with_max_steps(mut self, max_steps: u32) -> FixedStep {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// The time step, in seconds.
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    /// Measures the time elapsed since the last call with a `FrameTimer`,
+    /// and runs the update function for each full time step. While an
+    /// `EventReplayer` is active, the recorded frame duration is used.
+    ///
+    /// Outside of a replay, the first call only starts the measurement.
+    /// Returns the number of updates run.
+    pub fn advance<F: FnMut(f64)>(&mut self, update: F) -> u32 {
+        let elapsed = stm_sec(self.timer.laptime());
+        self.advance_by(elapsed, update)
+    }
+
+    /// Like `advance()`, with the elapsed time in seconds provided by the
+    /// caller, e.g. from a recording.
+    pub fn advance_by<F: FnMut(f64)>(&mut self, elapsed: f64, mut update: F) -> u32 {
+        self.accumulator += elapsed.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                // drop the time we can't catch up with
+                self.accumulator %= self.step;
+                break;
+            }
+            update(self.step);
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// The fraction of a time step accumulated after the last update,
+    /// in the range [0, 1).
+    pub fn alpha(&self) -> f64 {
+        self.accumulator / self.step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance(fixed: &mut FixedStep, elapsed: f64) -> u32 {
+        let step = fixed.step();
+        fixed.advance_by(elapsed, |dt| assert_eq!(dt, step))
+    }
+
+    #[test]
+    fn steps_per_call() {
+        let mut fixed = FixedStep::new(0.25);
+        assert_eq!(advance(&mut fixed, 0.125), 0);
+        assert_eq!(advance(&mut fixed, 0.125), 1);
+        assert_eq!(advance(&mut fixed, 0.5), 2);
+        assert_eq!(advance(&mut fixed, 0.375), 1);
+        assert_eq!(fixed.alpha(), 0.5);
+    }
+
+    #[test]
+    fn alpha_range() {
+        let mut fixed = FixedStep::new(0.25);
+        for _ in 0..20 {
+            advance(&mut fixed, 0.1);
+            let alpha = fixed.alpha();
+            assert!((0.0..1.0).contains(&alpha), "alpha {} out of range", alpha);
+        }
+    }
+
+    #[test]
+    fn max_steps_drops_time() {
+        let mut fixed = FixedStep::new(0.25).with_max_steps(3);
+
+        // a stall of 10.125 steps only runs 3 updates, and keeps the remainder
+        assert_eq!(advance(&mut fixed, 2.53125), 3);
+        assert_eq!(fixed.alpha(), 0.125);
+        assert_eq!(advance(&mut fixed, 0.0), 0);
+        assert_eq!(advance(&mut fixed, 0.25), 1);
+    }
+
+    #[test]
+    fn at_least_one_step() {
+        let mut fixed = FixedStep::new(0.25).with_max_steps(0);
+        assert_eq!(advance(&mut fixed, 1.0), 1);
+        assert_eq!(fixed.alpha(), 0.0);
+    }
+
+    #[test]
+    fn negative_elapsed_is_ignored() {
+        let mut fixed = FixedStep::new(0.25);
+        assert_eq!(advance(&mut fixed, 0.125), 0);
+        assert_eq!(advance(&mut fixed, -1.0), 0);
+        assert_eq!(fixed.alpha(), 0.5);
+        assert_eq!(advance(&mut fixed, 0.125), 1);
+    }
+}